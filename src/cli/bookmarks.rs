@@ -0,0 +1,94 @@
+use crate::cli::{read_demo, CliError};
+use clap::{ArgEnum, Args};
+use democutter::{try_bookmarks_with_options, HighlightOptions};
+use std::fmt::{Debug, Display};
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+#[derive(Args, Debug)]
+pub struct BookmarksArgs {
+    /// Path to the demo, or "-" to read from stdin
+    path: String,
+    /// Output format
+    #[clap(long, short, arg_enum, default_value = "json")]
+    format: Format,
+    /// Maximum number of seconds between kills to count them as a multi-kill
+    #[clap(long, default_value = "5")]
+    multi_kill_window: f32,
+    /// Add a killstreak highlight every this many kills without dying, 0 to disable
+    #[clap(long, default_value = "5")]
+    killstreak_interval: u32,
+    /// Minimum height in hammer units of a player above the position where they last stood on the ground
+    /// for a hit on them to count as an airshot
+    #[clap(long, default_value = "100")]
+    airshot_min_height: f32,
+    /// Minimum number of seconds a player has to be in the air for a hit on them to count as an airshot
+    #[clap(long, default_value = "0.25")]
+    airshot_min_airtime: f32,
+    /// Maximum number of seconds between the first and last death of a team wipe
+    #[clap(long, default_value = "10")]
+    team_wipe_window: f32,
+}
+
+pub fn run(args: BookmarksArgs) -> Result<(), CliError> {
+    let file = read_demo(&args.path)?;
+    let options = HighlightOptions {
+        multi_kill_window: args.multi_kill_window,
+        killstreak_interval: args.killstreak_interval,
+        airshot_min_height: args.airshot_min_height,
+        airshot_min_airtime: args.airshot_min_airtime,
+        team_wipe_window: args.team_wipe_window,
+    };
+    let bookmarks = try_bookmarks_with_options(&file, &options)?;
+    match args.format {
+        Format::Json => {
+            let json = serde_json::to_string_pretty(&bookmarks)
+                .expect("highlights can always be serialized");
+            println!("{}", json);
+        }
+        Format::Csv => {
+            println!("tick,user,name,steam_id,team,class,kind,start,end");
+            for highlight in bookmarks.highlights {
+                let ticks = highlight.source.ticks();
+                // highlights about a team or round leave the player columns empty
+                let player = highlight.player.as_ref();
+                println!(
+                    "{},{},{},{},{},{},{},{},{}",
+                    highlight.tick,
+                    optional_field(player.map(|player| player.user_id.0)),
+                    csv_field(player.map_or("", |player| player.name.as_str())),
+                    player.map_or("", |player| player.steam_id.as_str()),
+                    optional(player.and_then(|player| player.team)),
+                    optional(player.and_then(|player| player.class)),
+                    highlight.source.kind(),
+                    optional(ticks.map(|ticks| ticks.start)),
+                    optional(ticks.map(|ticks| ticks.end)),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Quote a field if it contains characters that would break the csv
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn optional_field<T: Display>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn optional<T: Debug>(value: Option<T>) -> String {
+    value
+        .map(|value| format!("{:?}", value).to_lowercase())
+        .unwrap_or_default()
+}
@@ -0,0 +1,209 @@
+use crate::cli::{demo_stem, read_demo, write_demo, CliError, DemoSink, RangeArg, STDIO};
+use clap::Args;
+use democutter::{
+    parse_type_name, tick_interval, try_cut_ranges_to_stream, try_cut_ranges_to_writer, verify_cut,
+    CutOptions, CutPoint, DemoIndex, TickRange, TypeFilter, DEFAULT_KEYFRAME_INTERVAL,
+};
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::path::Path;
+
+#[derive(Args, Debug)]
+pub struct CutArgs {
+    /// Path to the source demo, or "-" to read from stdin
+    path: String,
+    /// Start tick or time (HH:MM:SS.fff, MM:SS or 90s)
+    #[clap(conflicts_with_all = &["around", "range"])]
+    start: Option<CutPoint>,
+    /// End tick or time (HH:MM:SS.fff, MM:SS or 90s)
+    #[clap(requires = "start")]
+    end: Option<CutPoint>,
+    /// Ranges to cut as "start-end", can be given multiple times to splice several ranges together
+    #[clap(long, short, conflicts_with = "around")]
+    range: Vec<RangeArg>,
+    /// Cut around a tick or time instead of between a start and end
+    #[clap(long)]
+    around: Option<CutPoint>,
+    /// Seconds to include before the `--around` point
+    #[clap(long, default_value = "30")]
+    before: f32,
+    /// Seconds to include after the `--around` point
+    #[clap(long, default_value = "30")]
+    after: f32,
+    /// Output path, or "-" to write to stdout, defaults to "<demo>_cut.dem"
+    #[clap(long, short)]
+    output: Option<String>,
+    /// Build a sidecar index (`<demo>.idx`) if none exists, to speed up later cuts of the same demo
+    #[clap(long)]
+    index: bool,
+    /// Message types to drop from the output (e.g. "TempEntities")
+    #[clap(long, conflicts_with = "keep-message")]
+    drop_message: Vec<String>,
+    /// Only keep these message types, messages needed for playback are always kept
+    #[clap(long)]
+    keep_message: Vec<String>,
+    /// User message types to drop from the output (default: "CloseCaption")
+    #[clap(long, conflicts_with = "keep-user-message")]
+    drop_user_message: Vec<String>,
+    /// Only keep these user message types
+    #[clap(long)]
+    keep_user_message: Vec<String>,
+    /// Keep console commands from the source demo
+    #[clap(long)]
+    keep_console_commands: bool,
+    /// Drop voice data from the output
+    #[clap(long)]
+    drop_voice: bool,
+    /// Keep the tick numbers from the source demo
+    #[clap(long)]
+    preserve_ticks: bool,
+    /// Re-parse the output and compare it against the source demo
+    #[clap(long)]
+    verify: bool,
+}
+
+impl CutArgs {
+    fn options(&self) -> Result<CutOptions, CliError> {
+        let defaults = CutOptions::default();
+        Ok(CutOptions {
+            messages: type_filter(&self.drop_message, &self.keep_message)?
+                .unwrap_or(defaults.messages),
+            user_messages: type_filter(&self.drop_user_message, &self.keep_user_message)?
+                .unwrap_or(defaults.user_messages),
+            keep_console_commands: self.keep_console_commands,
+            keep_voice: !self.drop_voice,
+            preserve_ticks: self.preserve_ticks,
+        })
+    }
+
+    fn ranges(&self, interval: f32) -> Result<Vec<TickRange>, CliError> {
+        if let Some(around) = self.around {
+            let around = around.to_tick(interval);
+            let before = (self.before / interval).round() as u32;
+            let after = (self.after / interval).round() as u32;
+            return Ok(vec![TickRange::new(
+                around.saturating_sub(before),
+                around.saturating_add(after),
+            )]);
+        }
+        if let Some(start) = self.start {
+            let end = self
+                .end
+                .map(|end| end.to_tick(interval))
+                .unwrap_or(u32::MAX);
+            return Ok(vec![TickRange::new(start.to_tick(interval), end)]);
+        }
+        if self.range.is_empty() {
+            return Err(CliError::Usage(
+                "no range to cut, provide a start and end, --range or --around".into(),
+            ));
+        }
+        Ok(self
+            .range
+            .iter()
+            .map(|range| TickRange::new(range.start.to_tick(interval), range.end.to_tick(interval)))
+            .collect())
+    }
+
+    fn output_path(&self) -> String {
+        match &self.output {
+            Some(output) => output.clone(),
+            None if self.path == STDIO => STDIO.into(),
+            None => format!("{}_cut.dem", demo_stem(&self.path)),
+        }
+    }
+}
+
+fn type_filter<T: DeserializeOwned>(
+    drop: &[String],
+    keep: &[String],
+) -> Result<Option<TypeFilter<T>>, CliError> {
+    let parse = |names: &[String]| {
+        names
+            .iter()
+            .map(|name| parse_type_name(name).map_err(|e| CliError::Usage(e.to_string())))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    Ok(if !keep.is_empty() {
+        Some(TypeFilter::Keep(parse(keep)?))
+    } else if !drop.is_empty() {
+        Some(TypeFilter::Drop(parse(drop)?))
+    } else {
+        None
+    })
+}
+
+/// Load the sidecar index for the demo, building it if requested
+fn load_index(args: &CutArgs, file: &[u8]) -> Result<Option<DemoIndex>, CliError> {
+    if args.path == STDIO {
+        return Ok(None);
+    }
+    let path = Path::new(&args.path);
+    Ok(match DemoIndex::load_sidecar(path, file) {
+        Ok(Some(index)) => Some(index),
+        Ok(None) if args.index => {
+            let index = DemoIndex::new(file, DEFAULT_KEYFRAME_INTERVAL)?;
+            if let Err(e) = index.save_sidecar(path) {
+                eprintln!("Failed to save index: {}", e);
+            }
+            Some(index)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            eprintln!("Ignoring invalid index: {}", e);
+            None
+        }
+    })
+}
+
+pub fn run(args: CutArgs) -> Result<(), CliError> {
+    let file = read_demo(&args.path)?;
+    let interval = tick_interval(&file)?;
+    let ranges = args.ranges(interval)?;
+    let options = args.options()?;
+    if args.verify && ranges.len() > 1 {
+        return Err(CliError::Usage(
+            "--verify only supports cutting a single range".into(),
+        ));
+    }
+
+    let output_path = args.output_path();
+    if args.verify && output_path == STDIO {
+        return Err(CliError::Usage(
+            "--verify can't be used when writing to stdout".into(),
+        ));
+    }
+
+    let index = load_index(&args, &file)?;
+
+    let report = write_demo(&output_path, |sink| {
+        let (_, report) = match sink {
+            DemoSink::File(writer) => {
+                try_cut_ranges_to_writer(&file, writer, &ranges, index.as_ref(), &options)?
+            }
+            DemoSink::Stdout(writer) => {
+                try_cut_ranges_to_stream(&file, writer, &ranges, index.as_ref(), &options)?
+            }
+        };
+        Ok(report)
+    })?;
+    if report.clamped {
+        let clamped = report
+            .ranges
+            .iter()
+            .map(|range| format!("{}-{}", range.start, range.end))
+            .collect::<Vec<_>>()
+            .join(", ");
+        eprintln!("Range clamped to the demo, cut ticks {}", clamped);
+    }
+
+    if args.verify {
+        let output = fs::read(&output_path).map_err(CliError::read(&output_path))?;
+        let report = verify_cut(&file, &output, ranges[0])?;
+        if !report.is_ok() {
+            return Err(CliError::VerifyFailed(report));
+        }
+        eprintln!("{}", report);
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use crate::cli::{read_demo, CliError};
+use clap::Args;
+use democutter::{format_time, try_demo_info, DemoInfo, Team};
+use std::time::Duration;
+
+#[derive(Args, Debug)]
+pub struct InfoArgs {
+    /// Path to the demo, or "-" to read from stdin
+    path: String,
+    /// Output the information as json
+    #[clap(long)]
+    json: bool,
+}
+
+pub fn run(args: InfoArgs) -> Result<(), CliError> {
+    let file = read_demo(&args.path)?;
+    let info = try_demo_info(&file)?;
+
+    if args.json {
+        let json = serde_json::to_string_pretty(&info)
+            .expect("demo info can always be serialized to json");
+        println!("{}", json);
+    } else {
+        print_info(&info);
+    }
+    Ok(())
+}
+
+fn print_info(info: &DemoInfo) {
+    println!("Map:       {}", info.map);
+    println!("Server:    {}", info.server);
+    println!("Recorder:  {}", info.nick);
+    println!(
+        "Duration:  {}",
+        format_time(Duration::from_secs_f32(info.duration))
+    );
+    println!("Ticks:     {}", info.ticks);
+    println!("Tick rate: {:.0}", 1.0 / info.tick_interval);
+
+    println!();
+    println!("Players:");
+    for player in &info.players {
+        println!(
+            "  {:>4}  {:<20}  {}",
+            player.user_id.0, player.steam_id, player.name
+        );
+    }
+
+    println!();
+    println!("Rounds:");
+    for (i, round) in info.rounds.iter().enumerate() {
+        let end = round
+            .end_tick
+            .map(|tick| tick.to_string())
+            .unwrap_or_else(|| "-".into());
+        let winner = match round.winner {
+            Some(Team::Red) => "red",
+            Some(Team::Blue) => "blue",
+            None if round.end_tick.is_some() => "stalemate",
+            None => "",
+        };
+        println!(
+            "  {:>2}  {:>7}-{:<7}  {}",
+            i + 1,
+            round.start_tick,
+            end,
+            winner
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use democutter::{map_demo, CutError, CutPoint, TimeParseError, VerifyError, VerifyReport};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::ops::Deref;
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+pub mod bookmarks;
+pub mod cut;
+pub mod info;
+pub mod split;
+pub mod verify;
+
+/// Marker for reading from stdin or writing to stdout instead of a file
+const STDIO: &str = "-";
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("failed to read {path}: {source}")]
+    Read {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to write {path}: {source}")]
+    Write {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error(transparent)]
+    Cut(#[from] CutError),
+    #[error(transparent)]
+    Verify(#[from] VerifyError),
+    #[error("{0}")]
+    Usage(String),
+    #[error("{0}")]
+    VerifyFailed(VerifyReport),
+}
+
+impl CliError {
+    /// Exit code for the error
+    ///
+    /// - 1: the demo couldn't be processed
+    /// - 2: invalid arguments, the same as for errors reported by clap
+    /// - 3: reading or writing a file failed
+    /// - 4: the cut doesn't match the original demo
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Cut(_) | CliError::Verify(_) => 1,
+            CliError::Usage(_) => 2,
+            CliError::Read { .. } | CliError::Write { .. } => 3,
+            CliError::VerifyFailed(_) => 4,
+        }
+    }
+
+    fn read(path: &str) -> impl FnOnce(io::Error) -> Self + '_ {
+        move |source| CliError::Read {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    fn write(path: &str) -> impl FnOnce(io::Error) -> Self + '_ {
+        move |source| CliError::Write {
+            path: path.to_string(),
+            source,
+        }
+    }
+}
+
+impl From<TimeParseError> for CliError {
+    fn from(e: TimeParseError) -> Self {
+        CliError::Usage(e.to_string())
+    }
+}
+
+/// Demo data, either memory mapped from a file or read from stdin
+pub enum DemoData {
+    Mapped(Mmap),
+    Read(Vec<u8>),
+}
+
+impl Deref for DemoData {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            DemoData::Mapped(map) => map,
+            DemoData::Read(data) => data,
+        }
+    }
+}
+
+/// Read a demo from a path, or from stdin if the path is `-`
+pub fn read_demo(path: &str) -> Result<DemoData, CliError> {
+    if path == STDIO {
+        let mut data = Vec::new();
+        io::stdin()
+            .lock()
+            .read_to_end(&mut data)
+            .map_err(CliError::read("stdin"))?;
+        Ok(DemoData::Read(data))
+    } else {
+        map_demo(Path::new(path))
+            .map(DemoData::Mapped)
+            .map_err(CliError::read(path))
+    }
+}
+
+/// The destination of a demo written by [`write_demo`]
+pub enum DemoSink<'a> {
+    /// A file, which can seek back to write the header once the demo is finished
+    File(&'a mut BufWriter<File>),
+    /// Stdout, which can't seek
+    Stdout(&'a mut dyn Write),
+}
+
+/// Write a demo to a path, or to stdout if the path is `-`
+///
+/// `write` receives the sink to write the demo to. Output to stdout is streamed without buffering it,
+/// since stdout can't seek the demo has to be written in a way that doesn't need to go back to the header.
+pub fn write_demo<T, F>(path: &str, write: F) -> Result<T, CliError>
+where
+    F: FnOnce(DemoSink) -> Result<T, CliError>,
+{
+    if path == STDIO {
+        let stdout = io::stdout();
+        let mut writer = BufWriter::new(stdout.lock());
+        let result = write(DemoSink::Stdout(&mut writer))?;
+        writer.flush().map_err(CliError::write("stdout"))?;
+        Ok(result)
+    } else {
+        let file = File::create(path).map_err(CliError::write(path))?;
+        let mut writer = BufWriter::new(file);
+        let result = write(DemoSink::File(&mut writer))?;
+        writer.flush().map_err(CliError::write(path))?;
+        Ok(result)
+    }
+}
+
+/// A range given as `start-end`, with both ends as tick or time
+#[derive(Debug, Clone, Copy)]
+pub struct RangeArg {
+    pub start: CutPoint,
+    pub end: CutPoint,
+}
+
+impl FromStr for RangeArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("invalid range '{}', expected 'start-end'", s))?;
+        Ok(RangeArg {
+            start: start.parse().map_err(|e: TimeParseError| e.to_string())?,
+            end: end.parse().map_err(|e: TimeParseError| e.to_string())?,
+        })
+    }
+}
+
+/// Get the file name for a demo without the extension, for naming output files
+pub fn demo_stem(path: &str) -> &str {
+    if path == STDIO {
+        return "stdin";
+    }
+    Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("demo")
+}
@@ -0,0 +1,56 @@
+use crate::cli::{demo_stem, read_demo, CliError};
+use clap::Args;
+use democutter::{tick_interval, try_cut_batch_with, CutError, CutOptions, CutPoint, TickRange};
+use std::fs;
+use std::path::PathBuf;
+use tf_demo_parser::demo::header::Header;
+use tf_demo_parser::{Demo, ParseError};
+
+#[derive(Args, Debug)]
+pub struct SplitArgs {
+    /// Path to the demo, or "-" to read from stdin
+    path: String,
+    /// Length of every part, as tick count or time (HH:MM:SS.fff, MM:SS or 90s)
+    #[clap(long, short)]
+    every: CutPoint,
+    /// Directory to write the parts to, the parts are named "<demo>_<n>.dem"
+    #[clap(long, short, default_value = ".")]
+    output: PathBuf,
+}
+
+pub fn run(args: SplitArgs) -> Result<(), CliError> {
+    let file = read_demo(&args.path)?;
+    let demo = Demo::new(&file);
+    let header =
+        Header::read(&mut demo.get_stream()).map_err(|e| CutError::Header(ParseError::from(e)))?;
+    if header.ticks == 0 {
+        return Err(CliError::Usage(
+            "the demo header has no tick count, the demo might still be recording".into(),
+        ));
+    }
+    let interval = tick_interval(&file)?;
+    let length = args.every.to_tick(interval);
+    if length == 0 {
+        return Err(CliError::Usage("the part length can't be zero".into()));
+    }
+
+    let ranges = (0..header.ticks)
+        .step_by(length as usize)
+        .map(|start| TickRange::new(start, start.saturating_add(length).min(header.ticks)))
+        .collect::<Vec<_>>();
+
+    let stem = demo_stem(&args.path);
+    let mut result = Ok(());
+    try_cut_batch_with(&file, &ranges, &CutOptions::default(), |part, output| {
+        if result.is_ok() {
+            let path = args.output.join(format!("{}_{}.dem", stem, part + 1));
+            result = fs::write(&path, output).map_err(|source| CliError::Write {
+                path: path.display().to_string(),
+                source,
+            });
+        }
+    })?;
+    result?;
+    eprintln!("Split demo into {} parts", ranges.len());
+    Ok(())
+}
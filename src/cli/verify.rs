@@ -0,0 +1,29 @@
+use crate::cli::{read_demo, CliError};
+use clap::Args;
+use democutter::{tick_interval, verify_cut, CutPoint, TickRange};
+
+#[derive(Args, Debug)]
+pub struct VerifyArgs {
+    /// Path to the original demo
+    original: String,
+    /// Path to the cut demo
+    cut: String,
+    /// Start tick or time the demo was cut from
+    start: CutPoint,
+    /// End tick or time the demo was cut to
+    end: CutPoint,
+}
+
+pub fn run(args: VerifyArgs) -> Result<(), CliError> {
+    let original = read_demo(&args.original)?;
+    let cut = read_demo(&args.cut)?;
+    let interval = tick_interval(&original)?;
+    let range = TickRange::new(args.start.to_tick(interval), args.end.to_tick(interval));
+
+    let report = verify_cut(&original, &cut, range)?;
+    if !report.is_ok() {
+        return Err(CliError::VerifyFailed(report));
+    }
+    println!("{}", report);
+    Ok(())
+}
@@ -9,7 +9,7 @@ use tf_demo_parser::demo::packet::datatable::ClassId;
 use tf_demo_parser::demo::sendprop::SendPropIdentifier;
 use tf_demo_parser::ParserState;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ActiveEntities {
     entities: BTreeMap<EntityId, PacketEntity>,
     max_entities: u16,
@@ -80,10 +80,15 @@ impl ActiveEntities {
             .collect()
     }
 
+    /// Encode the active entities as a set of baseline and entity updates
+    ///
+    /// `previous` contains the entities that are already known to the client, these will be
+    /// removed or re-created if they don't match the current entities
     pub fn encode(
         mut self,
         state: &ParserState,
         delta: u32,
+        previous: &ActiveEntities,
     ) -> (
         impl IntoIterator<Item = PacketEntitiesMessage>,
         PacketEntitiesMessage,
@@ -111,8 +116,15 @@ impl ActiveEntities {
             ),
         ];
         for entity in self.entities.values_mut() {
+            let replaced = previous
+                .entities
+                .get(&entity.entity_index)
+                .map(|existing| existing.serial_number != entity.serial_number)
+                .unwrap_or_default();
             match state.instance_baselines[0].get(entity.entity_index) {
-                Some(baseline_entity) if baseline_entity.server_class == entity.server_class => {
+                Some(baseline_entity)
+                    if baseline_entity.server_class == entity.server_class && !replaced =>
+                {
                     entity.update_type = UpdateType::Preserve;
                 }
                 Some(_baseline_entity) => {
@@ -128,10 +140,13 @@ impl ActiveEntities {
             }
         }
 
-        // create deletes for all entities that have an updated baseline but are since removed
+        // create deletes for all entities that have an updated baseline or are known by the client but are since removed
         let removed_entities = self
             .baseline_ids(state)
             .into_iter()
+            .chain(previous.entities.keys().copied())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
             .filter(|id| !self.entities.contains_key(id))
             .collect::<Vec<_>>()
             .into_iter();
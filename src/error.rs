@@ -0,0 +1,121 @@
+use crate::writer::WriteError;
+use bitbuffer::BitError;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use tf_demo_parser::ParseError;
+use thiserror::Error;
+
+/// Location of the packet that was being processed when an error occurred
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PacketPosition {
+    /// Index of the packet in the source demo
+    pub index: usize,
+    /// Tick of the packet, or of the last successfully read packet if the packet itself couldn't be read
+    pub tick: u32,
+}
+
+impl Display for PacketPosition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "packet {} (tick {})", self.index, self.tick)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum CutError {
+    #[error("failed to read demo header: {0}")]
+    Header(#[source] ParseError),
+    #[error("malformed {position}: {source}")]
+    Parse {
+        position: PacketPosition,
+        #[source]
+        source: ParseError,
+    },
+    #[error("failed to process {position}: {source}")]
+    State {
+        position: PacketPosition,
+        #[source]
+        source: ParseError,
+    },
+    #[error("failed to encode {position}: {source}")]
+    Encode {
+        position: PacketPosition,
+        #[source]
+        source: ParseError,
+    },
+    #[error("failed to write output at {position}: {source}")]
+    Write {
+        position: PacketPosition,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to read demo: {0}")]
+    Input(#[source] io::Error),
+    #[error("none of the requested tick ranges are within the demo")]
+    EmptyRange,
+    #[error("cancelled at {position}")]
+    Cancelled { position: PacketPosition },
+}
+
+impl CutError {
+    pub(crate) fn parse<E: Into<ParseError>>(position: PacketPosition) -> impl FnOnce(E) -> Self {
+        move |source| CutError::Parse {
+            position,
+            source: source.into(),
+        }
+    }
+
+    pub(crate) fn state<E: Into<ParseError>>(position: PacketPosition) -> impl FnOnce(E) -> Self {
+        move |source| CutError::State {
+            position,
+            source: source.into(),
+        }
+    }
+
+    pub(crate) fn encode<E: Into<ParseError>>(position: PacketPosition) -> impl FnOnce(E) -> Self {
+        move |source| CutError::Encode {
+            position,
+            source: source.into(),
+        }
+    }
+
+    pub(crate) fn write(position: PacketPosition) -> impl FnOnce(WriteError) -> Self {
+        move |error| match error {
+            WriteError::Encode(source) => CutError::Encode { position, source },
+            WriteError::Io(source) => CutError::Write { position, source },
+        }
+    }
+
+    /// The packet that was being processed when the error occurred, if any
+    pub fn position(&self) -> Option<PacketPosition> {
+        match self {
+            CutError::Header(_) | CutError::Input(_) | CutError::EmptyRange => None,
+            CutError::Parse { position, .. }
+            | CutError::State { position, .. }
+            | CutError::Encode { position, .. }
+            | CutError::Write { position, .. }
+            | CutError::Cancelled { position } => Some(*position),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum IndexError {
+    #[error("failed to read or write index: {0}")]
+    Io(#[from] io::Error),
+    #[error("malformed index: {0}")]
+    Malformed(#[from] BitError),
+    #[error("not a demo index")]
+    InvalidMagic,
+    #[error("unsupported index version {0}")]
+    UnsupportedVersion(u16),
+    #[error("index was build for a different demo")]
+    Mismatch,
+}
+
+#[derive(Debug, Error)]
+pub enum VerifyError {
+    #[error("failed to parse original demo: {0}")]
+    Original(#[source] ParseError),
+    #[error("failed to parse cut demo: {0}")]
+    Cut(#[source] ParseError),
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use tf_demo_parser::demo::message::packetentities::{EntityId, PacketEntitiesMessage, UpdateType};
+use tf_demo_parser::demo::sendprop::{SendProp, SendPropIdentifier, SendPropValue};
+
+const PLAYER_COND: SendPropIdentifier =
+    SendPropIdentifier::new("DT_TFPlayerShared", "m_nPlayerCond");
+const FLAGS: SendPropIdentifier = SendPropIdentifier::new("DT_BasePlayer", "m_fFlags");
+const GROUND_ENTITY: SendPropIdentifier =
+    SendPropIdentifier::new("DT_BasePlayer", "m_hGroundEntity");
+const LOCAL_ORIGIN_Z: SendPropIdentifier =
+    SendPropIdentifier::new("DT_TFLocalPlayerExclusive", "m_vecOrigin[2]");
+const NON_LOCAL_ORIGIN_Z: SendPropIdentifier =
+    SendPropIdentifier::new("DT_TFNonLocalPlayerExclusive", "m_vecOrigin[2]");
+const LOCAL_CHARGE_LEVEL: SendPropIdentifier =
+    SendPropIdentifier::new("DT_LocalTFWeaponMedigunData", "m_flChargeLevel");
+const NON_LOCAL_CHARGE_LEVEL: SendPropIdentifier =
+    SendPropIdentifier::new("DT_TFWeaponMedigunDataNonLocal", "m_flChargeLevel");
+const OWNER_ENTITY: SendPropIdentifier = SendPropIdentifier::new("DT_BaseEntity", "m_hOwnerEntity");
+
+/// Set in the player conditions while a sniper is scoped in
+const COND_ZOOMED: i64 = 1 << 1;
+/// Set in the player flags while the player is standing on something
+const FL_ONGROUND: i64 = 1 << 0;
+/// Value of an entity handle that doesn't point to an entity
+const INVALID_HANDLE: i64 = (1 << 21) - 1;
+/// Bits of an entity handle that contain the entity index
+const HANDLE_INDEX_MASK: i64 = (1 << 11) - 1;
+
+/// State of a player entity that is needed for highlight detection
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PlayerEntity {
+    cond: i64,
+    zoom_start: Option<u32>,
+    flags: i64,
+    /// Handle of the entity the player is standing on, if the demo contains it
+    ground_entity: Option<i64>,
+    z: f32,
+    /// Height of the player the last time they were on the ground
+    ground_z: f32,
+    /// Tick at which the player left the ground, if they're in the air
+    airborne_start: Option<u32>,
+}
+
+impl PlayerEntity {
+    /// Tick at which the player scoped in, if they're currently scoped in
+    pub fn zoomed_since(&self) -> Option<u32> {
+        self.zoom_start
+    }
+
+    pub fn on_ground(&self) -> bool {
+        match self.ground_entity {
+            Some(handle) => handle != INVALID_HANDLE,
+            None => self.flags & FL_ONGROUND != 0,
+        }
+    }
+
+    /// Tick at which the player left the ground, if they're currently in the air
+    pub fn airborne_since(&self) -> Option<u32> {
+        self.airborne_start
+    }
+
+    /// Height of the player above where they last stood on the ground, 0 while the player is on the ground
+    ///
+    /// This is not the height above the ground below the player, a player that walked off a ledge is below the
+    /// position they last stood on, while a player that got knocked off a ledge can be high above the ground
+    /// below them without being high above the ledge.
+    pub fn height(&self) -> f32 {
+        if self.on_ground() {
+            0.0
+        } else {
+            self.z - self.ground_z
+        }
+    }
+
+    fn apply<'a>(&mut self, props: impl IntoIterator<Item = &'a SendProp>, tick: u32) {
+        for prop in props {
+            match &prop.value {
+                SendPropValue::Integer(cond) if prop.identifier == PLAYER_COND => {
+                    self.set_cond(*cond, tick)
+                }
+                SendPropValue::Integer(flags) if prop.identifier == FLAGS => self.flags = *flags,
+                SendPropValue::Integer(handle) if prop.identifier == GROUND_ENTITY => {
+                    self.ground_entity = Some(*handle)
+                }
+                SendPropValue::Float(z)
+                    if prop.identifier == LOCAL_ORIGIN_Z
+                        || prop.identifier == NON_LOCAL_ORIGIN_Z =>
+                {
+                    self.z = *z
+                }
+                _ => {}
+            }
+        }
+        if self.on_ground() {
+            self.ground_z = self.z;
+            self.airborne_start = None;
+        } else if self.airborne_start.is_none() {
+            self.airborne_start = Some(tick);
+        }
+    }
+
+    fn set_cond(&mut self, cond: i64, tick: u32) {
+        let was_zoomed = self.cond & COND_ZOOMED != 0;
+        let zoomed = cond & COND_ZOOMED != 0;
+        if zoomed && !was_zoomed {
+            self.zoom_start = Some(tick);
+        } else if !zoomed {
+            self.zoom_start = None;
+        }
+        self.cond = cond;
+    }
+}
+
+fn is_charge_level(identifier: SendPropIdentifier) -> bool {
+    identifier == LOCAL_CHARGE_LEVEL || identifier == NON_LOCAL_CHARGE_LEVEL
+}
+
+/// State of a medigun entity
+#[derive(Debug, Default, Clone, Copy)]
+struct Medigun {
+    /// Entity of the medic holding the medigun
+    owner: Option<EntityId>,
+    /// Charge between 0 and 1
+    charge: f32,
+}
+
+impl Medigun {
+    fn apply<'a>(&mut self, props: impl IntoIterator<Item = &'a SendProp>) {
+        for prop in props {
+            match &prop.value {
+                SendPropValue::Float(charge) if is_charge_level(prop.identifier) => {
+                    self.charge = *charge
+                }
+                SendPropValue::Integer(handle) if prop.identifier == OWNER_ENTITY => {
+                    self.owner = (*handle != INVALID_HANDLE)
+                        .then(|| EntityId::from((*handle & HANDLE_INDEX_MASK) as u32));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Tracks the props of the player entities and the mediguns they hold
+#[derive(Default)]
+pub struct PlayerEntities {
+    players: HashMap<EntityId, PlayerEntity>,
+    mediguns: HashMap<EntityId, Medigun>,
+}
+
+impl PlayerEntities {
+    pub fn handle_message(&mut self, message: &PacketEntitiesMessage, tick: u32) {
+        for entity in &message.entities {
+            match entity.update_type {
+                UpdateType::Leave | UpdateType::Delete => {
+                    self.remove(entity.entity_index);
+                    continue;
+                }
+                // the entity index can be reused, an entering entity starts from its baseline
+                UpdateType::Enter => self.remove(entity.entity_index),
+                _ => {}
+            }
+            let baseline = match entity.update_type {
+                UpdateType::Enter => entity.baseline_props.as_slice(),
+                _ => &[],
+            };
+            let props = || baseline.iter().chain(entity.props.iter());
+            let is_player = self.players.contains_key(&entity.entity_index)
+                || props().any(|prop| prop.identifier == FLAGS || prop.identifier == PLAYER_COND);
+            if is_player {
+                self.players
+                    .entry(entity.entity_index)
+                    .or_default()
+                    .apply(props(), tick);
+            }
+            let is_medigun = self.mediguns.contains_key(&entity.entity_index)
+                || props().any(|prop| is_charge_level(prop.identifier));
+            if is_medigun {
+                self.mediguns
+                    .entry(entity.entity_index)
+                    .or_default()
+                    .apply(props());
+            }
+        }
+        for removed in &message.removed_entities {
+            self.remove(*removed);
+        }
+    }
+
+    fn remove(&mut self, entity: EntityId) {
+        self.players.remove(&entity);
+        self.mediguns.remove(&entity);
+    }
+
+    pub fn get(&self, entity: EntityId) -> Option<&PlayerEntity> {
+        self.players.get(&entity)
+    }
+
+    /// Charge of the medigun held by a player, between 0 and 1
+    pub fn charge(&self, player: EntityId) -> Option<f32> {
+        self.mediguns
+            .values()
+            .find(|medigun| medigun.owner == Some(player))
+            .map(|medigun| medigun.charge)
+    }
+}
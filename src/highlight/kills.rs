@@ -0,0 +1,103 @@
+use crate::highlight::{Detection, HighlightOptions, HighlightSource, MultiKill};
+use crate::player::{PlayerState, PlayerTracker};
+use crate::TickRange;
+use std::collections::BTreeMap;
+use tf_demo_parser::demo::parser::analyser::UserId;
+
+/// Kills made by a player since their last death
+#[derive(Default)]
+struct Streak {
+    kills: u32,
+    start_tick: u32,
+    /// Number of kills made in quick succession
+    chain: u32,
+    chain_start: u32,
+    chain_end: u32,
+    /// State of the player at the last kill of the chain
+    player: Option<PlayerState>,
+}
+
+impl Streak {
+    /// End the current chain of kills, returning it if it was a multi-kill
+    fn end_chain(&mut self) -> Option<Detection> {
+        let kind = MultiKill::from_kills(self.chain);
+        self.chain = 0;
+        let ticks = TickRange::new(self.chain_start, self.chain_end);
+        kind.map(|kind| Detection {
+            tick: ticks.start,
+            player: self.player.clone(),
+            source: HighlightSource::MultiKill { kind, ticks },
+        })
+    }
+}
+
+/// Finds multi-kills and killstreak milestones from the kills of each player
+#[derive(Default)]
+pub struct KillTracker {
+    streaks: BTreeMap<UserId, Streak>,
+}
+
+impl KillTracker {
+    /// Handle a player death
+    ///
+    /// Chains of kills that expired before `tick` have to be ended with [`end_expired`](Self::end_expired) first
+    pub fn handle_death(
+        &mut self,
+        tick: u32,
+        attacker: UserId,
+        victim: UserId,
+        options: &HighlightOptions,
+        players: &PlayerTracker,
+        found: &mut Vec<Detection>,
+    ) {
+        // the streak of the victim ends with their death, this includes suicides
+        if let Some(mut streak) = self.streaks.remove(&victim) {
+            found.extend(streak.end_chain());
+        }
+
+        if attacker == victim || attacker.0 == 0 {
+            return;
+        }
+
+        let streak = self.streaks.entry(attacker).or_default();
+        streak.kills += 1;
+        if streak.kills == 1 {
+            streak.start_tick = tick;
+        }
+        streak.chain += 1;
+        if streak.chain == 1 {
+            streak.chain_start = tick;
+        }
+        streak.chain_end = tick;
+        streak.player = Some(players.state(attacker));
+
+        let interval = options.killstreak_interval;
+        if interval > 0 && streak.kills % interval == 0 {
+            found.push(Detection {
+                tick,
+                player: streak.player.clone(),
+                source: HighlightSource::Killstreak {
+                    kills: streak.kills,
+                    ticks: TickRange::new(streak.start_tick, tick),
+                },
+            });
+        }
+    }
+
+    /// End all chains of kills that can't be continued anymore at `tick`,
+    /// `window` is the maximum number of ticks between kills of a multi-kill
+    pub fn end_expired(&mut self, tick: u32, window: u32, found: &mut Vec<Detection>) {
+        for streak in self.streaks.values_mut() {
+            if streak.chain > 0 && tick.saturating_sub(streak.chain_end) > window {
+                found.extend(streak.end_chain());
+            }
+        }
+    }
+
+    /// End all chains of kills at the end of the demo
+    pub fn finish(&mut self, found: &mut Vec<Detection>) {
+        for streak in self.streaks.values_mut() {
+            found.extend(streak.end_chain());
+        }
+    }
+}
@@ -0,0 +1,554 @@
+use crate::highlight::entities::PlayerEntities;
+use crate::highlight::kills::KillTracker;
+use crate::highlight::rounds::RoundTracker;
+use crate::highlight::special::{is_airshot_weapon, is_scoped_weapon, special_kill};
+use crate::highlight::uber::UberTracker;
+use crate::player::{Class, DemoPlayer, PlayerState, PlayerTracker, Team};
+use crate::time::DEFAULT_TICK_INTERVAL;
+use crate::{duration_to_ticks, TickRange};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::mem::take;
+use std::time::Duration;
+use tf_demo_parser::demo::gameevent_gen::{
+    GameEvent, PlayerDeathEvent, PlayerHurtEvent, RocketJumpEvent, RocketJumpLandedEvent,
+    StickyJumpEvent, StickyJumpLandedEvent,
+};
+use tf_demo_parser::demo::message::gameevent::GameEventMessage;
+use tf_demo_parser::demo::message::usermessage::UserMessage;
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::demo::packet::stringtable::StringTableEntry;
+use tf_demo_parser::demo::parser::analyser::UserId;
+use tf_demo_parser::demo::parser::MessageHandler;
+use tf_demo_parser::{MessageType, ParserState};
+
+mod entities;
+mod kills;
+mod rounds;
+mod special;
+mod uber;
+
+/// The highlights found in a demo, with the players that appear in them
+#[derive(Debug, Serialize)]
+pub struct Bookmarks {
+    pub highlights: Vec<Highlight>,
+    pub players: Vec<DemoPlayer>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Highlight {
+    pub tick: u32,
+    /// The player that made the highlight, `None` for highlights about a team or round
+    pub player: Option<PlayerState>,
+    pub source: HighlightSource,
+}
+
+/// Set in `death_flags` when a spy fakes their death with the dead ringer
+const DEATH_FLAG_FEIGN_DEATH: u16 = 32;
+
+/// Maximum time between scoping in and the shot for a headshot to count as a quickscope
+const QUICKSCOPE_TIME: Duration = Duration::from_millis(500);
+
+/// Maximum time between a medic taking heavy damage and using their uber for the uber to count as forced
+const FORCED_UBER_TIME: Duration = Duration::from_secs(1);
+
+/// Time to include before a highlight in the suggested range to cut
+pub const HIGHLIGHT_BEFORE: Duration = Duration::from_secs(30);
+/// Time to include after a highlight in the suggested range to cut
+pub const HIGHLIGHT_AFTER: Duration = Duration::from_secs(15);
+
+impl Highlight {
+    /// Range of ticks to cut to show the highlight with some context
+    pub fn suggested_range(&self, interval_per_tick: f32) -> TickRange {
+        let before = duration_to_ticks(HIGHLIGHT_BEFORE, interval_per_tick);
+        let after = duration_to_ticks(HIGHLIGHT_AFTER, interval_per_tick);
+        let ticks = self
+            .source
+            .ticks()
+            .unwrap_or_else(|| TickRange::new(self.tick, self.tick));
+        TickRange::new(
+            ticks.start.saturating_sub(before),
+            ticks.end.saturating_add(after),
+        )
+    }
+}
+
+/// What made a highlight, serialized with the name from [`HighlightSource::kind`] in a `kind` field
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum HighlightSource {
+    Prec,
+    /// A hit with a rocket or grenade launcher on a player that is high up in the air
+    AirShot {
+        attacker: PlayerState,
+        victim: PlayerState,
+        /// Height of the victim above the ground they last stood on
+        height: f32,
+    },
+    /// Multiple kills by the same player in quick succession
+    MultiKill {
+        /// Serialized as `multiKill`, `kind` is taken by the kind of the highlight
+        #[serde(rename = "multiKill")]
+        kind: MultiKill,
+        ticks: TickRange,
+    },
+    /// The player reached a number of kills without dying
+    Killstreak {
+        kills: u32,
+        ticks: TickRange,
+    },
+    Headshot {
+        kill: Kill,
+        /// How a sniper was scoped in, `None` for headshots with other weapons
+        scope: Option<Scope>,
+    },
+    Backstab(Kill),
+    /// A kill with the market gardener while rocket jumping
+    MarketGarden(Kill),
+    /// A kill with a reflected projectile
+    Reflect(Kill),
+    TauntKill(Kill),
+    Telefrag(Kill),
+    UberUsed(Uber),
+    /// An uber used shortly after the medic took heavy damage
+    ForcedUber(Uber),
+    /// A medic died with a full charge
+    UberDropped(Uber),
+    /// The team of the medic became the only team with a full charge
+    UberAdvantage(Uber),
+    /// A control point was captured, the highlight is made by the first of the cappers
+    PointCaptured {
+        team: Team,
+        round: u32,
+        /// Index of the control point
+        point: u8,
+        name: String,
+        cappers: Vec<PlayerState>,
+    },
+    /// A player stopped the capture of a control point
+    PointBlocked {
+        /// Team of the player that blocked the capture
+        team: Team,
+        point: u8,
+        name: String,
+    },
+    IntelCaptured {
+        team: Team,
+    },
+    RoundStart {
+        round: u32,
+    },
+    RoundWin {
+        team: Team,
+        round: u32,
+    },
+    RoundStalemate {
+        round: u32,
+    },
+    /// All living players of a team died in quick succession
+    TeamWipe {
+        team: Team,
+        round: u32,
+        ticks: TickRange,
+    },
+}
+
+impl HighlightSource {
+    /// Name of the kind of highlight, as used in the web ui
+    pub fn kind(&self) -> &'static str {
+        match self {
+            HighlightSource::Prec => "prec",
+            HighlightSource::AirShot { .. } => "airShot",
+            HighlightSource::MultiKill { .. } => "multiKill",
+            HighlightSource::Killstreak { .. } => "killstreak",
+            HighlightSource::Headshot { .. } => "headshot",
+            HighlightSource::Backstab(_) => "backstab",
+            HighlightSource::MarketGarden(_) => "marketGarden",
+            HighlightSource::Reflect(_) => "reflect",
+            HighlightSource::TauntKill(_) => "tauntKill",
+            HighlightSource::Telefrag(_) => "telefrag",
+            HighlightSource::UberUsed(_) => "uberUsed",
+            HighlightSource::ForcedUber(_) => "forcedUber",
+            HighlightSource::UberDropped(_) => "uberDropped",
+            HighlightSource::UberAdvantage(_) => "uberAdvantage",
+            HighlightSource::PointCaptured { .. } => "pointCaptured",
+            HighlightSource::PointBlocked { .. } => "pointBlocked",
+            HighlightSource::IntelCaptured { .. } => "intelCaptured",
+            HighlightSource::RoundStart { .. } => "roundStart",
+            HighlightSource::RoundWin { .. } => "roundWin",
+            HighlightSource::RoundStalemate { .. } => "roundStalemate",
+            HighlightSource::TeamWipe { .. } => "teamWipe",
+        }
+    }
+
+    /// The ticks spanned by the highlight, for highlights that aren't a single moment
+    pub fn ticks(&self) -> Option<TickRange> {
+        match self {
+            HighlightSource::MultiKill { ticks, .. }
+            | HighlightSource::Killstreak { ticks, .. }
+            | HighlightSource::TeamWipe { ticks, .. } => Some(*ticks),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MultiKill {
+    Double,
+    Triple,
+    /// Four or more kills
+    Quad,
+}
+
+impl MultiKill {
+    fn from_kills(kills: u32) -> Option<Self> {
+        match kills {
+            0 | 1 => None,
+            2 => Some(MultiKill::Double),
+            3 => Some(MultiKill::Triple),
+            _ => Some(MultiKill::Quad),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Kill {
+    pub attacker: PlayerState,
+    pub victim: PlayerState,
+    pub weapon: String,
+}
+
+/// How a sniper was scoped in when making a headshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Scoped,
+    /// Scoped in shortly before the shot
+    Quickscope,
+    Noscope,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Uber {
+    pub medic: PlayerState,
+    pub team: Option<Team>,
+    /// Charge of the medigun between 0 and 1
+    pub charge: f32,
+}
+
+/// Settings for the highlight detection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HighlightOptions {
+    /// Maximum number of seconds between two kills to count them towards the same multi-kill
+    pub multi_kill_window: f32,
+    /// Add a killstreak highlight every time a player reaches a multiple of this many kills without dying,
+    /// 0 to disable killstreak highlights
+    pub killstreak_interval: u32,
+    /// Minimum height in hammer units of a player above the position where they last stood on the ground
+    /// for a hit on them to count as an airshot
+    pub airshot_min_height: f32,
+    /// Minimum number of seconds a player has to be in the air for a hit on them to count as an airshot
+    pub airshot_min_airtime: f32,
+    /// Maximum number of seconds between the first and last death of a team wipe
+    pub team_wipe_window: f32,
+}
+
+impl Default for HighlightOptions {
+    fn default() -> Self {
+        HighlightOptions {
+            multi_kill_window: 5.0,
+            killstreak_interval: 5,
+            airshot_min_height: 100.0,
+            airshot_min_airtime: 0.25,
+            team_wipe_window: 10.0,
+        }
+    }
+}
+
+/// A highlight found by one of the detectors
+struct Detection {
+    tick: u32,
+    /// State of the player that made the highlight at the time it was made
+    player: Option<PlayerState>,
+    source: HighlightSource,
+}
+
+/// Finds the highlights in a demo, for use as analyser with a `DemoParser`
+pub struct HighlightAnalyser {
+    options: HighlightOptions,
+    interval_per_tick: f32,
+    highlights: Vec<Highlight>,
+    explosive_jumping: HashMap<UserId, bool>,
+    players: PlayerTracker,
+    entities: PlayerEntities,
+    kills: KillTracker,
+    ubers: UberTracker,
+    rounds: RoundTracker,
+    detections: Vec<Detection>,
+}
+
+impl Default for HighlightAnalyser {
+    fn default() -> Self {
+        HighlightAnalyser::new(HighlightOptions::default())
+    }
+}
+
+impl HighlightAnalyser {
+    pub fn new(options: HighlightOptions) -> Self {
+        HighlightAnalyser {
+            options,
+            interval_per_tick: DEFAULT_TICK_INTERVAL,
+            highlights: Vec::new(),
+            explosive_jumping: HashMap::new(),
+            players: PlayerTracker::default(),
+            entities: PlayerEntities::default(),
+            kills: KillTracker::default(),
+            ubers: UberTracker::default(),
+            rounds: RoundTracker::default(),
+            detections: Vec::new(),
+        }
+    }
+
+    fn is_explosive_jumping(&self, user: UserId) -> bool {
+        self.explosive_jumping
+            .get(&user)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn push(&mut self, tick: u32, player: Option<PlayerState>, source: HighlightSource) {
+        self.highlights.push(Highlight {
+            tick,
+            player,
+            source,
+        })
+    }
+
+    /// Add the highlights found by the detectors
+    fn push_detections(&mut self) {
+        for detection in take(&mut self.detections) {
+            self.push(detection.tick, detection.player, detection.source);
+        }
+    }
+
+    /// How the attacker was scoped in for a headshot, `None` if the attacker isn't a sniper using a rifle
+    fn scope(&self, attacker: UserId, weapon: &str, tick: u32) -> Option<Scope> {
+        if self.players.class(attacker) != Some(Class::Sniper) || !is_scoped_weapon(weapon) {
+            return None;
+        }
+        let entity = self.entities.get(self.players.entity(attacker)?)?;
+        let quickscope = duration_to_ticks(QUICKSCOPE_TIME, self.interval_per_tick);
+        Some(match entity.zoomed_since() {
+            None => Scope::Noscope,
+            Some(start) if tick.saturating_sub(start) <= quickscope => Scope::Quickscope,
+            Some(_) => Scope::Scoped,
+        })
+    }
+
+    fn handle_death(&mut self, death: &PlayerDeathEvent, tick: u32) {
+        let attacker = UserId::from(death.attacker);
+        let victim = UserId::from(death.user_id);
+        self.kills.handle_death(
+            tick,
+            attacker,
+            victim,
+            &self.options,
+            &self.players,
+            &mut self.detections,
+        );
+        let wipe_window = self.team_wipe_window();
+        let wipe = self.rounds.handle_death(victim, tick, wipe_window);
+        self.detections.extend(wipe);
+
+        if attacker == victim || attacker.0 == 0 {
+            return;
+        }
+        let kill = Kill {
+            attacker: self.players.state(attacker),
+            victim: self.players.state(victim),
+            weapon: death.weapon.to_string(),
+        };
+        let scope = self.scope(attacker, &kill.weapon, tick);
+        let explosive_jumping = self.is_explosive_jumping(attacker);
+        if let Some(source) = special_kill(death, kill, scope, explosive_jumping) {
+            self.detections.push(Detection {
+                tick,
+                player: Some(self.players.state(attacker)),
+                source,
+            });
+        }
+    }
+
+    fn handle_hurt(&mut self, hit: &PlayerHurtEvent, tick: u32) {
+        self.ubers
+            .handle_hurt(UserId::from(hit.user_id), tick, hit.health);
+        if !is_airshot_weapon(hit.weapon_id) {
+            return;
+        }
+        let victim = UserId::from(hit.user_id);
+        let victim_entity = self
+            .players
+            .entity(victim)
+            .and_then(|entity| self.entities.get(entity));
+        let airborne =
+            victim_entity.and_then(|entity| Some((entity.height(), entity.airborne_since()?)));
+        let (height, airborne_since) = match airborne {
+            Some(airborne) => airborne,
+            None => return,
+        };
+        if height < self.options.airshot_min_height
+            || tick.saturating_sub(airborne_since) < self.airshot_min_airtime()
+        {
+            return;
+        }
+
+        let attacker = UserId::from(hit.attacker);
+        self.detections.push(Detection {
+            tick,
+            player: Some(self.players.state(attacker)),
+            source: HighlightSource::AirShot {
+                attacker: self.players.state(attacker),
+                victim: self.players.state(victim),
+                height,
+            },
+        });
+    }
+
+    fn multi_kill_window(&self) -> u32 {
+        (self.options.multi_kill_window / self.interval_per_tick).round() as u32
+    }
+
+    fn team_wipe_window(&self) -> u32 {
+        (self.options.team_wipe_window / self.interval_per_tick).round() as u32
+    }
+
+    fn airshot_min_airtime(&self) -> u32 {
+        (self.options.airshot_min_airtime / self.interval_per_tick).round() as u32
+    }
+}
+
+impl MessageHandler for HighlightAnalyser {
+    type Output = Bookmarks;
+
+    fn does_handle(_message_type: MessageType) -> bool {
+        true
+    }
+
+    fn handle_message(&mut self, message: &Message, tick: u32) {
+        // multi-kills are found as soon as the window for the next kill has passed
+        let window = self.multi_kill_window();
+        self.kills.end_expired(tick, window, &mut self.detections);
+        if let Message::GameEvent(GameEventMessage { event, .. }) = message {
+            self.players.handle_event(event);
+            self.rounds
+                .handle_event(event, tick, &self.players, &mut self.detections);
+        }
+        match message {
+            Message::ServerInfo(info) => self.interval_per_tick = info.interval_per_tick,
+            Message::GameEvent(GameEventMessage {
+                event: GameEvent::PlayerDeath(death),
+                ..
+            }) if death.death_flags & DEATH_FLAG_FEIGN_DEATH == 0 => {
+                self.handle_death(death, tick);
+            }
+            Message::GameEvent(GameEventMessage {
+                event: GameEvent::PlayerChargeDeployed(deploy),
+                ..
+            }) => {
+                let forced_ticks = duration_to_ticks(FORCED_UBER_TIME, self.interval_per_tick);
+                let detection = self.ubers.handle_deploy(
+                    deploy.user_id.into(),
+                    tick,
+                    forced_ticks,
+                    &self.players,
+                    &self.entities,
+                );
+                self.detections.push(detection);
+            }
+            Message::GameEvent(GameEventMessage {
+                event: GameEvent::MedicDeath(death),
+                ..
+            }) => {
+                let detection = self.ubers.handle_medic_death(
+                    death.user_id.into(),
+                    tick,
+                    death.charged,
+                    &self.players,
+                    &self.entities,
+                );
+                self.detections.extend(detection);
+            }
+            Message::PacketEntities(message) => {
+                self.entities.handle_message(message, tick);
+                self.ubers.update_advantage(
+                    tick,
+                    &self.players,
+                    &self.entities,
+                    &mut self.detections,
+                );
+            }
+            Message::GameEvent(GameEventMessage {
+                event: GameEvent::PlayerHurt(hit),
+                ..
+            }) if hit.attacker != hit.user_id && hit.attacker != 0 => {
+                self.handle_hurt(hit, tick);
+            }
+            Message::GameEvent(GameEventMessage {
+                event: GameEvent::RocketJump(RocketJumpEvent { user_id, .. }),
+                ..
+            })
+            | Message::GameEvent(GameEventMessage {
+                event: GameEvent::StickyJump(StickyJumpEvent { user_id, .. }),
+                ..
+            }) => {
+                let user_id = (*user_id).into();
+                self.explosive_jumping.insert(user_id, true);
+            }
+            Message::GameEvent(GameEventMessage {
+                event: GameEvent::RocketJumpLanded(RocketJumpLandedEvent { user_id, .. }),
+                ..
+            })
+            | Message::GameEvent(GameEventMessage {
+                event: GameEvent::StickyJumpLanded(StickyJumpLandedEvent { user_id, .. }),
+                ..
+            }) => {
+                let user_id = (*user_id).into();
+                self.explosive_jumping.insert(user_id, false);
+            }
+            Message::GameEvent(GameEventMessage {
+                event: GameEvent::PlayerSpawn(spawn),
+                ..
+            }) => {
+                let user_id = spawn.user_id.into();
+                self.explosive_jumping.insert(user_id, false);
+            }
+            Message::UserMessage(UserMessage::SayText2(text)) => {
+                if text.text == "[P-REC] Bookmark." {
+                    let player = self.players.state(text.client);
+                    self.push(tick, Some(player), HighlightSource::Prec);
+                }
+            }
+            _ => {}
+        }
+        self.push_detections();
+    }
+
+    fn handle_string_entry(&mut self, table: &str, _index: usize, entry: &StringTableEntry) {
+        self.players.handle_string_entry(table, entry);
+    }
+
+    fn into_output(mut self, _state: &ParserState) -> Self::Output {
+        self.kills.finish(&mut self.detections);
+        self.push_detections();
+        // multi-kills are only found once the next kill is too late to continue them
+        self.highlights.sort_by_key(|highlight| highlight.tick);
+        Bookmarks {
+            highlights: self.highlights,
+            players: self.players.players(),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::highlight::{Detection, HighlightSource};
+use crate::player::{PlayerTracker, Team};
+use crate::TickRange;
+use std::collections::HashMap;
+use tf_demo_parser::demo::gameevent_gen::{
+    GameEvent, TeamPlayCaptureBlockedEvent, TeamPlayFlagEventEvent, TeamPlayPointCapturedEvent,
+};
+use tf_demo_parser::demo::message::packetentities::EntityId;
+use tf_demo_parser::demo::parser::analyser::UserId;
+
+/// `event_type` of a flag event when the intel is captured
+const FLAG_CAPTURED: u16 = 2;
+/// Minimum number of deaths within the window for a team wipe, or the team size for smaller teams
+const MIN_WIPE_DEATHS: usize = 3;
+
+struct LifeState {
+    team: Team,
+    alive: bool,
+    death_tick: Option<u32>,
+}
+
+/// Finds objective and round events and team wipes
+#[derive(Default)]
+pub struct RoundTracker {
+    /// Number of the current round, starting at 1 for the round the demo starts in
+    round: u32,
+    running: bool,
+    lives: HashMap<UserId, LifeState>,
+}
+
+impl RoundTracker {
+    /// Handle the round and objective events and keep track of which players are alive
+    pub fn handle_event(
+        &mut self,
+        event: &GameEvent,
+        tick: u32,
+        players: &PlayerTracker,
+        found: &mut Vec<Detection>,
+    ) {
+        match event {
+            GameEvent::TeamPlayRoundStart(_) => {
+                // a restart while a round is running replaces the running round
+                if !self.running {
+                    self.round += 1;
+                }
+                self.running = true;
+                found.push(round_detection(
+                    tick,
+                    HighlightSource::RoundStart { round: self.round },
+                ));
+            }
+            GameEvent::TeamPlayRoundWin(win) => {
+                let round = self.end_round();
+                if let Some(team) = Team::from_number(win.team.into()) {
+                    found.push(round_detection(
+                        tick,
+                        HighlightSource::RoundWin { team, round },
+                    ));
+                }
+            }
+            GameEvent::TeamPlayRoundStalemate(_) => {
+                let round = self.end_round();
+                found.push(round_detection(
+                    tick,
+                    HighlightSource::RoundStalemate { round },
+                ));
+            }
+            GameEvent::TeamPlayPointCaptured(capture) => {
+                found.extend(self.point_captured(capture, tick, players))
+            }
+            GameEvent::TeamPlayCaptureBlocked(block) => {
+                found.extend(point_blocked(block, tick, players))
+            }
+            GameEvent::TeamPlayFlagEvent(flag) => found.extend(intel_captured(flag, tick, players)),
+            GameEvent::PlayerSpawn(spawn) => {
+                if let Some(team) = Team::from_number(spawn.team) {
+                    self.lives.insert(
+                        spawn.user_id.into(),
+                        LifeState {
+                            team,
+                            alive: true,
+                            death_tick: None,
+                        },
+                    );
+                }
+            }
+            GameEvent::PlayerTeam(change) => {
+                // players that switch team or leave die without a death event
+                self.lives.remove(&change.user_id.into());
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle a player dying, returning a team wipe if it was the last living player of their team
+    ///
+    /// `wipe_window` is the maximum number of ticks between the first and last death of a team wipe
+    pub fn handle_death(
+        &mut self,
+        victim: UserId,
+        tick: u32,
+        wipe_window: u32,
+    ) -> Option<Detection> {
+        let life = self.lives.get_mut(&victim)?;
+        if !life.alive {
+            return None;
+        }
+        life.alive = false;
+        life.death_tick = Some(tick);
+        let team = life.team;
+
+        let team_lives = self
+            .lives
+            .values()
+            .filter(|life| life.team == team)
+            .collect::<Vec<_>>();
+        if team_lives.iter().any(|life| life.alive) {
+            return None;
+        }
+        let window_start = tick.saturating_sub(wipe_window);
+        let deaths = team_lives
+            .iter()
+            .filter_map(|life| life.death_tick)
+            .filter(|death_tick| *death_tick >= window_start)
+            .collect::<Vec<_>>();
+        if deaths.len() < MIN_WIPE_DEATHS.min(team_lives.len()) {
+            return None;
+        }
+        let first_death = deaths.iter().copied().min().unwrap_or(tick);
+        Some(Detection {
+            tick: first_death,
+            player: None,
+            source: HighlightSource::TeamWipe {
+                team,
+                round: self.round.max(1),
+                ticks: TickRange::new(first_death, tick),
+            },
+        })
+    }
+
+    /// End the current round, returning its number
+    fn end_round(&mut self) -> u32 {
+        // the demo started during the round
+        if !self.running {
+            self.round += 1;
+        }
+        self.running = false;
+        self.round
+    }
+
+    fn point_captured(
+        &self,
+        capture: &TeamPlayPointCapturedEvent,
+        tick: u32,
+        players: &PlayerTracker,
+    ) -> Option<Detection> {
+        let team = Team::from_number(capture.team.into())?;
+        // the cappers are sent as a string with a character for the entity index of every player
+        let cappers = capture
+            .cappers
+            .to_string()
+            .bytes()
+            .filter_map(|entity| players.user_for_entity(EntityId::from(entity as u32)))
+            .collect::<Vec<_>>();
+        let cappers = cappers
+            .into_iter()
+            .map(|user| players.state(user))
+            .collect::<Vec<_>>();
+        Some(Detection {
+            tick,
+            player: cappers.first().cloned(),
+            source: HighlightSource::PointCaptured {
+                team,
+                round: self.round.max(1),
+                point: capture.cp,
+                name: capture.cp_name.to_string(),
+                cappers,
+            },
+        })
+    }
+}
+
+fn point_blocked(
+    block: &TeamPlayCaptureBlockedEvent,
+    tick: u32,
+    players: &PlayerTracker,
+) -> Option<Detection> {
+    let blocker = players.user_for_entity(EntityId::from(block.blocker as u32))?;
+    Some(Detection {
+        tick,
+        player: Some(players.state(blocker)),
+        source: HighlightSource::PointBlocked {
+            team: players.team(blocker)?,
+            point: block.cp,
+            name: block.cp_name.to_string(),
+        },
+    })
+}
+
+fn intel_captured(
+    flag: &TeamPlayFlagEventEvent,
+    tick: u32,
+    players: &PlayerTracker,
+) -> Option<Detection> {
+    if flag.event_type != FLAG_CAPTURED {
+        return None;
+    }
+    let capper = players.user_for_entity(EntityId::from(flag.player as u32))?;
+    Some(Detection {
+        tick,
+        player: Some(players.state(capper)),
+        source: HighlightSource::IntelCaptured {
+            team: players.team(capper)?,
+        },
+    })
+}
+
+fn round_detection(tick: u32, source: HighlightSource) -> Detection {
+    Detection {
+        tick,
+        player: None,
+        source,
+    }
+}
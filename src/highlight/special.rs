@@ -0,0 +1,76 @@
+use crate::highlight::{HighlightSource, Kill, Scope};
+use tf_demo_parser::demo::gameevent_gen::PlayerDeathEvent;
+
+// values of `custom_kill` in the death event, from `ETFDmgCustom`
+const CUSTOM_HEADSHOT: u16 = 1;
+const CUSTOM_BACKSTAB: u16 = 2;
+/// A headshot with the machina that went through another player first
+const CUSTOM_PENETRATE_HEADSHOT: u16 = 14;
+const CUSTOM_TELEFRAG: u16 = 16;
+const CUSTOM_HEADSHOT_DECAPITATION: u16 = 51;
+const CUSTOM_TAUNT_KILLS: &[u16] = &[
+    7,  // hadouken
+    9,  // high noon
+    10, // grand slam
+    13, // fencing
+    15, // arrow stab
+    21, // grenade
+    24, // barbarian swing
+    29, // uberslice
+    33, // engineer guitar smash
+    38, // engineer arm
+    52, // armageddon
+    62, // guitar riff
+];
+
+// values of `weapon_id` in the hurt event, from `ETFWeaponType`
+const WEAPON_ROCKETLAUNCHER: u16 = 22;
+const WEAPON_GRENADELAUNCHER: u16 = 23;
+const WEAPON_ROCKETLAUNCHER_DIRECTHIT: u16 = 65;
+/// Weapons for which a hit on an airborne player counts as an airshot
+const AIRSHOT_WEAPONS: &[u16] = &[
+    WEAPON_ROCKETLAUNCHER,
+    WEAPON_GRENADELAUNCHER,
+    WEAPON_ROCKETLAUNCHER_DIRECTHIT,
+];
+
+const MARKET_GARDENER: &str = "market_gardener";
+/// Prefix of the weapon name for kills by reflected projectiles
+const DEFLECT_PREFIX: &str = "deflect_";
+
+/// Find the kind of special kill for a death event
+///
+/// `scope` is how the attacker was scoped in for headshots and `explosive_jumping` whether the attacker
+/// was blast jumping at the time of the kill
+pub fn special_kill(
+    death: &PlayerDeathEvent,
+    kill: Kill,
+    scope: Option<Scope>,
+    explosive_jumping: bool,
+) -> Option<HighlightSource> {
+    Some(match death.custom_kill {
+        CUSTOM_HEADSHOT | CUSTOM_PENETRATE_HEADSHOT | CUSTOM_HEADSHOT_DECAPITATION => {
+            HighlightSource::Headshot { kill, scope }
+        }
+        CUSTOM_BACKSTAB => HighlightSource::Backstab(kill),
+        CUSTOM_TELEFRAG => HighlightSource::Telefrag(kill),
+        custom if CUSTOM_TAUNT_KILLS.contains(&custom) => HighlightSource::TauntKill(kill),
+        _ if death.weapon.starts_with(DEFLECT_PREFIX) => HighlightSource::Reflect(kill),
+        _ if death.weapon == MARKET_GARDENER && explosive_jumping => {
+            HighlightSource::MarketGarden(kill)
+        }
+        _ => return None,
+    })
+}
+
+/// Whether the weapon is a sniper rifle, for which headshots are reported as scoped, quickscoped or noscoped
+pub fn is_scoped_weapon(weapon: &str) -> bool {
+    !(weapon.contains("bow") || weapon.contains("arrow") || weapon == "ambassador")
+}
+
+/// Whether a hit with the weapon on an airborne player counts as an airshot
+///
+/// Only direct hits from rocket and grenade launchers take enough skill to be a highlight
+pub fn is_airshot_weapon(weapon_id: u16) -> bool {
+    AIRSHOT_WEAPONS.contains(&weapon_id)
+}
@@ -0,0 +1,126 @@
+use crate::highlight::entities::PlayerEntities;
+use crate::highlight::{Detection, HighlightSource, Uber};
+use crate::player::{PlayerTracker, Team};
+use std::collections::HashMap;
+use tf_demo_parser::demo::parser::analyser::UserId;
+
+/// A medic with at most this much health after being hit shortly before deploying was forced to use their uber
+const FORCED_UBER_HEALTH: u16 = 75;
+/// Charge at which a medic is ready to deploy
+const FULL_CHARGE: f32 = 1.0;
+
+/// Finds used, forced and dropped ubers and changes in which team has the uber advantage
+#[derive(Default)]
+pub struct UberTracker {
+    /// Last tick each player was hurt by an enemy, with their health after the hit
+    last_hurt: HashMap<UserId, (u32, u16)>,
+    /// The last team that was the only one with a full charge
+    advantage: Option<Team>,
+}
+
+impl UberTracker {
+    pub fn handle_hurt(&mut self, victim: UserId, tick: u32, health: u16) {
+        self.last_hurt.insert(victim, (tick, health));
+    }
+
+    /// Handle a medic deploying their charge, `forced_ticks` is how recently the medic needs to have been
+    /// hurt for the uber to count as forced
+    pub fn handle_deploy(
+        &mut self,
+        medic: UserId,
+        tick: u32,
+        forced_ticks: u32,
+        players: &PlayerTracker,
+        entities: &PlayerEntities,
+    ) -> Detection {
+        let forced = match self.last_hurt.get(&medic) {
+            Some((hurt_tick, health)) => {
+                tick.saturating_sub(*hurt_tick) <= forced_ticks && *health <= FORCED_UBER_HEALTH
+            }
+            None => false,
+        };
+        let uber = uber(medic, FULL_CHARGE, players, entities);
+        Detection {
+            tick,
+            player: Some(uber.medic.clone()),
+            source: if forced {
+                HighlightSource::ForcedUber(uber)
+            } else {
+                HighlightSource::UberUsed(uber)
+            },
+        }
+    }
+
+    /// Handle the death of a medic, returning a highlight if they died with a full charge
+    pub fn handle_medic_death(
+        &mut self,
+        medic: UserId,
+        tick: u32,
+        charged: bool,
+        players: &PlayerTracker,
+        entities: &PlayerEntities,
+    ) -> Option<Detection> {
+        self.last_hurt.remove(&medic);
+        let uber = uber(medic, 0.0, players, entities);
+        (charged || uber.charge >= FULL_CHARGE).then(|| Detection {
+            tick,
+            player: Some(uber.medic.clone()),
+            source: HighlightSource::UberDropped(Uber {
+                charge: uber.charge.max(FULL_CHARGE),
+                ..uber
+            }),
+        })
+    }
+
+    /// Check if the uber advantage moved from one team to the other
+    pub fn update_advantage(
+        &mut self,
+        tick: u32,
+        players: &PlayerTracker,
+        entities: &PlayerEntities,
+        found: &mut Vec<Detection>,
+    ) {
+        let ready = players
+            .medics()
+            .filter(|(_, _, entity)| {
+                entities
+                    .charge(*entity)
+                    .map_or(false, |charge| charge >= FULL_CHARGE)
+            })
+            .collect::<Vec<_>>();
+        let red_ready = ready.iter().find(|(_, team, _)| *team == Team::Red);
+        let blue_ready = ready.iter().find(|(_, team, _)| *team == Team::Blue);
+        let (team, medic) = match (red_ready, blue_ready) {
+            (Some((medic, team, _)), None) | (None, Some((medic, team, _))) => (*team, *medic),
+            _ => return,
+        };
+
+        if self.advantage.map_or(false, |advantage| advantage != team) {
+            let uber = uber(medic, FULL_CHARGE, players, entities);
+            found.push(Detection {
+                tick,
+                player: Some(uber.medic.clone()),
+                source: HighlightSource::UberAdvantage(uber),
+            });
+        }
+        self.advantage = Some(team);
+    }
+}
+
+/// The uber state of a medic, `default_charge` is used if the charge of the medigun isn't known
+fn uber(
+    medic: UserId,
+    default_charge: f32,
+    players: &PlayerTracker,
+    entities: &PlayerEntities,
+) -> Uber {
+    let charge = players
+        .entity(medic)
+        .and_then(|entity| entities.charge(entity))
+        .unwrap_or(default_charge);
+    Uber {
+        medic: players.state(medic),
+        team: players.team(medic),
+        charge,
+    }
+}
@@ -0,0 +1,344 @@
+use crate::entity::ActiveEntities;
+use crate::error::{CutError, IndexError, PacketPosition};
+use crate::progress::ProgressTracker;
+use crate::reader::PacketReader;
+use crate::{msg_packet, read_header, read_start_packets, skip_start, NetTimings, StartState};
+use bitbuffer::{BitReadBuffer, BitReadStream, BitWriteStream, LittleEndian};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tf_demo_parser::demo::header::Header;
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::demo::packet::Packet;
+use tf_demo_parser::demo::parser::{DemoHandler, Encode, NullHandler};
+use tf_demo_parser::{Demo, Parse, ParseError, ParserState, Stream};
+
+/// Default number of ticks between keyframes, one minute at 66 ticks per second
+pub const DEFAULT_KEYFRAME_INTERVAL: u32 = 66 * 60;
+
+/// Magic bytes at the start of a serialized index
+const INDEX_MAGIC: &[u8; 4] = b"DCIX";
+/// Version of the serialized index format, bump when the format or the encoded state changes
+const INDEX_VERSION: u16 = 2;
+/// Number of bytes from the end of the demo included in the hash
+const HASH_TAIL_LENGTH: usize = 64 * 1024;
+
+/// Snapshots of the state needed to start a cut, taken at regular intervals through a demo
+///
+/// Building the index takes a single pass over the demo, after which cuts made with the index
+/// can resume from the nearest keyframe instead of processing the demo from the start.
+pub struct DemoIndex {
+    input_length: usize,
+    input_hash: u64,
+    keyframes: Vec<Keyframe>,
+}
+
+/// The state of the demo after processing the packet at `position`
+pub struct Keyframe {
+    /// Position of the last packet included in the keyframe
+    pub position: PacketPosition,
+    /// Byte offset of the first packet after the keyframe
+    pub offset: usize,
+    pub server_tick: u32,
+    pub last_delta: u32,
+    pub entity_max: u16,
+    /// Timing of the net ticks before the keyframe, used for the net ticks of the synthesized packets
+    net_timings: NetTimings,
+    /// Entity and string table state, encoded as message packets
+    state: Vec<u8>,
+}
+
+impl DemoIndex {
+    /// Build an index for the demo with a keyframe every `interval` ticks
+    pub fn new(input: &[u8], interval: u32) -> Result<Self, CutError> {
+        let demo = Demo::new(&input);
+        let mut stream = demo.get_stream();
+        let header = read_header(&mut stream)?;
+
+        let mut handler = DemoHandler::default();
+        handler.handle_header(&header);
+
+        let mut packets = PacketReader::new(stream);
+        let mut start_state = StartState::default();
+        let mut keyframes = Vec::new();
+        let mut next_keyframe = interval;
+
+        loop {
+            skip_start(
+                &mut handler,
+                &mut packets,
+                &mut start_state,
+                next_keyframe,
+                &mut ProgressTracker::disabled(),
+            )?;
+            if packets.is_ended() {
+                break;
+            }
+            let position = packets.position();
+            keyframes.push(
+                Keyframe::capture(
+                    &start_state,
+                    &handler.state_handler,
+                    position,
+                    packets.offset(),
+                )
+                .map_err(CutError::encode(position))?,
+            );
+            next_keyframe = position.tick + interval.max(1);
+        }
+
+        Ok(DemoIndex {
+            input_length: input.len(),
+            input_hash: input_hash(input),
+            keyframes,
+        })
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Check if the index was build for the input
+    pub fn matches(&self, input: &[u8]) -> bool {
+        self.input_length == input.len() && self.input_hash == input_hash(input)
+    }
+
+    /// Get the last keyframe from before `tick`
+    pub fn keyframe_before(&self, tick: u32) -> Option<&Keyframe> {
+        let count = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.position.tick < tick);
+        count.checked_sub(1).map(|index| &self.keyframes[index])
+    }
+}
+
+/// Serialization of the index
+///
+/// The serialized index is stored next to the demo as sidecar file (`match.dem.idx`)
+/// and starts with a magic value, format version and the size and hash of the demo it was build for.
+impl DemoIndex {
+    /// Get the path of the sidecar index file for a demo
+    pub fn sidecar_path(demo_path: &Path) -> PathBuf {
+        let mut path = demo_path.as_os_str().to_owned();
+        path.push(".idx");
+        PathBuf::from(path)
+    }
+
+    /// Load the sidecar index for a demo, if one exists and matches the demo
+    pub fn load_sidecar(demo_path: &Path, input: &[u8]) -> Result<Option<Self>, IndexError> {
+        let data = match fs::read(Self::sidecar_path(demo_path)) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        match Self::from_bytes(&data, input) {
+            Ok(index) => Ok(Some(index)),
+            Err(IndexError::Mismatch | IndexError::UnsupportedVersion(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Save the index as sidecar file for a demo
+    pub fn save_sidecar(&self, demo_path: &Path) -> Result<(), IndexError> {
+        Ok(fs::write(Self::sidecar_path(demo_path), self.to_bytes())?)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        {
+            let mut stream = BitWriteStream::new(&mut data, LittleEndian);
+            self.write(&mut stream)
+                .expect("writing to a vec can't fail");
+        }
+        data
+    }
+
+    fn write(&self, stream: &mut BitWriteStream<LittleEndian>) -> bitbuffer::Result<()> {
+        stream.write_bytes(INDEX_MAGIC)?;
+        stream.write(&INDEX_VERSION)?;
+        stream.write(&(self.input_length as u64))?;
+        stream.write(&self.input_hash)?;
+        stream.write(&(self.keyframes.len() as u32))?;
+        for keyframe in &self.keyframes {
+            stream.write(&(keyframe.position.index as u64))?;
+            stream.write(&keyframe.position.tick)?;
+            stream.write(&(keyframe.offset as u64))?;
+            stream.write(&keyframe.server_tick)?;
+            stream.write(&keyframe.last_delta)?;
+            stream.write(&keyframe.entity_max)?;
+            stream.write(&(keyframe.net_timings.recent.len() as u32))?;
+            for (tick, frame_time, std_dev) in &keyframe.net_timings.recent {
+                stream.write(tick)?;
+                stream.write(frame_time)?;
+                stream.write(std_dev)?;
+            }
+            stream.write(&(keyframe.state.len() as u32))?;
+            stream.write_bytes(&keyframe.state)?;
+        }
+        Ok(())
+    }
+
+    /// Load a serialized index, verifying that it was build for `input`
+    pub fn from_bytes(data: &[u8], input: &[u8]) -> Result<Self, IndexError> {
+        let mut stream = BitReadStream::new(BitReadBuffer::new(data, LittleEndian));
+        if stream.read_bytes(INDEX_MAGIC.len())?.as_ref() != INDEX_MAGIC {
+            return Err(IndexError::InvalidMagic);
+        }
+        let version: u16 = stream.read()?;
+        if version != INDEX_VERSION {
+            return Err(IndexError::UnsupportedVersion(version));
+        }
+        let input_length = stream.read::<u64>()? as usize;
+        let input_hash = stream.read()?;
+        if input_length != input.len() || input_hash != self::input_hash(input) {
+            return Err(IndexError::Mismatch);
+        }
+
+        let count: u32 = stream.read()?;
+        let keyframes = (0..count)
+            .map(|_| {
+                let position = PacketPosition {
+                    index: stream.read::<u64>()? as usize,
+                    tick: stream.read()?,
+                };
+                let offset = stream.read::<u64>()? as usize;
+                let server_tick = stream.read()?;
+                let last_delta = stream.read()?;
+                let entity_max = stream.read()?;
+                let timing_count: u32 = stream.read()?;
+                let net_timings = NetTimings {
+                    recent: (0..timing_count)
+                        .map(|_| Ok((stream.read()?, stream.read()?, stream.read()?)))
+                        .collect::<bitbuffer::Result<_>>()?,
+                };
+                let state_length = stream.read::<u32>()? as usize;
+                let state = stream.read_bytes(state_length)?.into_owned();
+                if offset > input.len() {
+                    return Err(IndexError::Mismatch);
+                }
+                Ok(Keyframe {
+                    position,
+                    offset,
+                    server_tick,
+                    last_delta,
+                    entity_max,
+                    net_timings,
+                    state,
+                })
+            })
+            .collect::<Result<Vec<_>, IndexError>>()?;
+
+        Ok(DemoIndex {
+            input_length,
+            input_hash,
+            keyframes,
+        })
+    }
+}
+
+/// FNV-1a hash over the length, header and tail of the demo
+///
+/// Hashing the entire demo would take about as long as building the index,
+/// the header and tail are enough to catch a demo that has been replaced or was still being recorded.
+fn input_hash(input: &[u8]) -> u64 {
+    let header = &input[0..input.len().min(1072)];
+    let tail = &input[input.len().saturating_sub(HASH_TAIL_LENGTH)..];
+    (input.len() as u64)
+        .to_le_bytes()
+        .iter()
+        .chain(header)
+        .chain(tail)
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+impl Keyframe {
+    fn capture(
+        start_state: &StartState,
+        state: &ParserState,
+        position: PacketPosition,
+        offset: usize,
+    ) -> Result<Self, ParseError> {
+        let (baseline_updates, entity_update, removed_update) = start_state
+            .entities
+            .clone()
+            .encode(state, start_state.last_delta, &ActiveEntities::default());
+        let messages = start_state
+            .table_updates
+            .clone()
+            .encode()
+            .into_iter()
+            .map(Message::UpdateStringTable)
+            .chain(baseline_updates.into_iter().map(Message::PacketEntities))
+            .chain([
+                Message::PacketEntities(entity_update),
+                Message::PacketEntities(removed_update),
+            ]);
+
+        let mut data = Vec::new();
+        {
+            let mut stream = BitWriteStream::new(&mut data, LittleEndian);
+            for message in messages {
+                msg_packet(position.tick, vec![message]).encode(&mut stream, state)?;
+            }
+        }
+
+        Ok(Keyframe {
+            position,
+            offset,
+            server_tick: start_state.server_tick,
+            last_delta: start_state.last_delta,
+            entity_max: start_state.entity_max,
+            net_timings: start_state.net_timings.clone(),
+            state: data,
+        })
+    }
+
+    /// Reset the handler and start state to the keyframe and continue reading packets from there
+    ///
+    /// If the start packets of the demo haven't been read yet, they are read from the start of `stream`
+    pub(crate) fn restore<'a>(
+        &'a self,
+        header: &Header,
+        stream: Stream<'a>,
+        handler: &mut DemoHandler<'a, NullHandler>,
+        packets: &mut PacketReader<'a>,
+        start_state: &mut StartState<'a>,
+    ) -> Result<(), CutError> {
+        *handler = DemoHandler::default();
+        handler.handle_header(header);
+
+        let start_packets = if start_state.start_packets.is_empty() {
+            read_start_packets(handler, &mut PacketReader::new(stream.clone()))?
+        } else {
+            let start_packets = std::mem::take(&mut start_state.start_packets);
+            for packet in start_packets.iter().cloned() {
+                handler
+                    .handle_packet(packet)
+                    .map_err(CutError::state(self.position))?;
+            }
+            start_packets
+        };
+        *start_state = StartState {
+            start_packets,
+            ..StartState::default()
+        };
+
+        let mut state_stream = BitReadStream::new(BitReadBuffer::new(&self.state, LittleEndian));
+        while state_stream.bits_left() > 0 {
+            let packet = Packet::parse(&mut state_stream, &handler.state_handler)
+                .map_err(CutError::parse(self.position))?;
+            start_state.handle_packet(&packet, &handler.state_handler);
+            handler
+                .handle_packet(packet)
+                .map_err(CutError::state(self.position))?;
+        }
+        start_state.server_tick = self.server_tick;
+        start_state.last_delta = self.last_delta;
+        start_state.entity_max = self.entity_max;
+        start_state.net_timings = self.net_timings.clone();
+
+        *packets = PacketReader::resume(stream, self.offset, self.position)?;
+        Ok(())
+    }
+}
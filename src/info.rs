@@ -0,0 +1,122 @@
+use crate::player::{DemoPlayer, PlayerTracker, Team};
+use serde::Serialize;
+use tf_demo_parser::demo::gameevent_gen::GameEvent;
+use tf_demo_parser::demo::message::gameevent::GameEventMessage;
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::demo::packet::stringtable::StringTableEntry;
+use tf_demo_parser::demo::parser::MessageHandler;
+use tf_demo_parser::{MessageType, ParserState};
+
+/// Information about a demo, as shown before picking the ticks to cut
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DemoInfo {
+    pub map: String,
+    pub server: String,
+    /// Name of the player or tv bot that recorded the demo
+    pub nick: String,
+    /// Duration of the demo in seconds
+    pub duration: f32,
+    pub ticks: u32,
+    /// Tick interval in seconds
+    pub tick_interval: f32,
+    pub players: Vec<DemoPlayer>,
+    pub rounds: Vec<Round>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Round {
+    pub start_tick: u32,
+    /// Tick at which the round was won or ended in a stalemate, `None` if the demo ends during the round
+    pub end_tick: Option<u32>,
+    /// The winning team, `None` for a stalemate or unfinished round
+    pub winner: Option<Team>,
+}
+
+/// Collects the players and rounds in a demo
+#[derive(Default)]
+pub struct InfoAnalyser {
+    players: PlayerTracker,
+    rounds: Vec<Round>,
+    interval: Option<f32>,
+    last_tick: u32,
+}
+
+impl InfoAnalyser {
+    fn end_round(&mut self, tick: u32, winner: Option<Team>) {
+        match self.rounds.last_mut() {
+            Some(round) if round.end_tick.is_none() => {
+                round.end_tick = Some(tick);
+                round.winner = winner;
+            }
+            // the demo started during the round
+            _ => self.rounds.push(Round {
+                start_tick: 0,
+                end_tick: Some(tick),
+                winner,
+            }),
+        }
+    }
+}
+
+/// The players and rounds of a demo, with the tick interval from the `ServerInfo` message and the last tick seen
+pub struct InfoOutput {
+    pub players: Vec<DemoPlayer>,
+    pub rounds: Vec<Round>,
+    pub interval: Option<f32>,
+    pub last_tick: u32,
+}
+
+impl MessageHandler for InfoAnalyser {
+    type Output = InfoOutput;
+
+    fn does_handle(message_type: MessageType) -> bool {
+        matches!(
+            message_type,
+            MessageType::GameEvent | MessageType::ServerInfo | MessageType::NetTick
+        )
+    }
+
+    fn handle_message(&mut self, message: &Message, tick: u32) {
+        // every message packet starts with a net tick, so this covers the last packet of the demo
+        self.last_tick = self.last_tick.max(tick);
+        match message {
+            Message::ServerInfo(info) => self.interval = Some(info.interval_per_tick),
+            Message::GameEvent(GameEventMessage { event, .. }) => match event {
+                GameEvent::TeamPlayRoundStart(_) => {
+                    // a restart while a round is running replaces the running round
+                    if let Some(round) = self.rounds.last() {
+                        if round.end_tick.is_none() {
+                            self.rounds.pop();
+                        }
+                    }
+                    self.rounds.push(Round {
+                        start_tick: tick,
+                        end_tick: None,
+                        winner: None,
+                    })
+                }
+                GameEvent::TeamPlayRoundWin(win) => {
+                    self.end_round(tick, Team::from_number(win.team.into()));
+                }
+                GameEvent::TeamPlayRoundStalemate(_) => self.end_round(tick, None),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn handle_string_entry(&mut self, table: &str, _index: usize, entry: &StringTableEntry) {
+        self.players.handle_string_entry(table, entry);
+    }
+
+    fn into_output(self, _state: &ParserState) -> Self::Output {
+        InfoOutput {
+            players: self.players.players(),
+            rounds: self.rounds,
+            interval: self.interval,
+            last_tick: self.last_tick,
+        }
+    }
+}
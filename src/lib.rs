@@ -1,22 +1,59 @@
 #![allow(unused_imports)]
 
 mod entity;
+mod error;
 mod highlight;
+mod index;
+mod info;
 mod mutate;
+mod options;
+mod output;
+mod player;
+mod progress;
+mod reader;
 mod string_tables;
+mod time;
 mod utils;
+mod verify;
+mod writer;
 
 use crate::entity::ActiveEntities;
-use crate::highlight::{Highlight, HighlightAnalyser};
+pub use crate::error::{CutError, IndexError, PacketPosition, VerifyError};
+pub use crate::highlight::{
+    Bookmarks, Highlight, HighlightAnalyser, HighlightOptions, HighlightSource, Kill, MultiKill,
+    Scope, Uber, HIGHLIGHT_AFTER, HIGHLIGHT_BEFORE,
+};
+pub use crate::index::{DemoIndex, Keyframe, DEFAULT_KEYFRAME_INTERVAL};
+use crate::info::InfoAnalyser;
+pub use crate::info::{DemoInfo, Round};
 use crate::mutate::{MessageMutator, MutatorList, PacketMutator};
+pub use crate::options::{parse_type_name, CutOptions, TypeFilter, UnknownTypeError};
+use crate::output::CutOutput;
+pub use crate::player::{Class, DemoPlayer, PlayerState, Team};
+use crate::progress::ProgressTracker;
+pub use crate::progress::{Progress, PROGRESS_INTERVAL};
+use crate::reader::PacketReader;
 use crate::string_tables::StringTablesUpdates;
-use crate::utils::set_panic_hook;
+pub use crate::time::{
+    duration_to_ticks, format_time, parse_time, ticks_to_duration, CutPoint, TimeParseError,
+};
+use crate::time::{header_tick_interval, DEFAULT_TICK_INTERVAL};
+use crate::utils::{js_error, set_panic_hook};
+pub use crate::verify::{verify_cut, Divergence, DivergenceKind, VerifyReport};
+use crate::writer::rewrite_header;
 use bitbuffer::{BitRead, BitWrite, BitWriteStream, LittleEndian};
+use serde::{Deserialize, Serialize};
 use std::cmp::{max, min};
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, VecDeque};
 use std::convert::TryInto;
+use std::io::{self, Cursor, Seek, Write};
 use std::iter::once;
 use std::mem::take;
+use std::num::NonZeroU32;
+use std::ops::ControlFlow;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use std::time::Duration;
 use tf_demo_parser::demo::header::Header;
 use tf_demo_parser::demo::message::packetentities::{EntityId, PacketEntitiesMessage, UpdateType};
 use tf_demo_parser::demo::message::usermessage::{UserMessage, UserMessageType};
@@ -24,8 +61,10 @@ use tf_demo_parser::demo::message::{Message, NetTickMessage};
 use tf_demo_parser::demo::packet::message::{MessagePacket, MessagePacketMeta};
 use tf_demo_parser::demo::packet::stop::StopPacket;
 use tf_demo_parser::demo::packet::{Packet, PacketType};
-use tf_demo_parser::demo::parser::{DemoHandler, Encode, NullHandler, RawPacketStream};
-use tf_demo_parser::{Demo, DemoParser, MessageType};
+use tf_demo_parser::demo::parser::{
+    DemoHandler, Encode, MessageHandler, NullHandler, RawPacketStream,
+};
+use tf_demo_parser::{Demo, DemoParser, MessageType, ParseError, ParserState, Stream};
 use wasm_bindgen::prelude::*;
 use web_sys::console;
 
@@ -33,6 +72,51 @@ use web_sys::console;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+/// A range of ticks to cut from the demo, both ends inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl TickRange {
+    pub fn new(start: u32, end: u32) -> Self {
+        TickRange { start, end }
+    }
+
+    /// Number of ticks in the output for this range
+    pub fn len(&self) -> u32 {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Information about a finished cut
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CutReport {
+    /// The ranges of the source demo that ended up in the output, after clamping them to the demo
+    /// and merging overlapping ranges
+    pub ranges: Vec<TickRange>,
+    /// Whether any of the requested ranges had to be shortened to fit within the demo
+    pub clamped: bool,
+    /// Number of packets in the output, including the synthesized packets
+    pub packets_written: u32,
+    /// Number of packets synthesized to rebuild the state at the start of the segments
+    pub synthesized_packets: u32,
+    /// Number of net ticks synthesized for the rebuilt state
+    pub synthesized_ticks: u32,
+    /// Number of entities reconstructed at the start of the segments
+    pub entities: u32,
+    /// Number of string table entries reconstructed at the start of the segments
+    pub string_table_entries: u32,
+    /// Size of the output in bytes
+    pub output_size: u64,
+}
+
 const PRESERVE_PACKETS: &[PacketType] = &[
     PacketType::Signon,
     PacketType::DataTables,
@@ -40,136 +124,612 @@ const PRESERVE_PACKETS: &[PacketType] = &[
     PacketType::SyncTick,
 ];
 
-#[wasm_bindgen]
+/// Cut the demo to the given tick range
+///
+/// Panics if the demo can't be parsed, see [`try_cut`] for a fallible version
 pub fn cut(input: &[u8], start_tick: u32, end_tick: u32) -> Vec<u8> {
+    try_cut(input, start_tick, end_tick).unwrap()
+}
+
+#[wasm_bindgen(js_name = cut)]
+pub fn cut_js(input: &[u8], start_tick: u32, end_tick: u32) -> Result<Vec<u8>, JsValue> {
     set_panic_hook();
-    let mut out_buffer = Vec::with_capacity(input.len());
-    {
-        let mut out_stream = BitWriteStream::new(&mut out_buffer, LittleEndian);
+    try_cut(input, start_tick, end_tick).map_err(js_error)
+}
 
-        let demo = Demo::new(&input);
-        let mut stream = demo.get_stream();
-        let mut header = Header::read(&mut stream).unwrap();
+pub fn try_cut(input: &[u8], start_tick: u32, end_tick: u32) -> Result<Vec<u8>, CutError> {
+    try_cut_ranges(input, &[TickRange::new(start_tick, end_tick)])
+}
 
-        let start_tick = min(header.ticks - 10, start_tick);
-        let end_tick = min(header.ticks, end_tick);
-        let duration_per_tick = header.ticks as f32 / header.duration;
+/// Cut the demo to the given tick range, with options for which messages to copy
+pub fn try_cut_with_options(
+    input: &[u8],
+    start_tick: u32,
+    end_tick: u32,
+    options: &CutOptions,
+) -> Result<Vec<u8>, CutError> {
+    let ranges = [TickRange::new(start_tick, end_tick)];
+    Ok(try_cut_with_report(input, &ranges, options)?.0)
+}
 
-        header.ticks = end_tick - start_tick;
-        header.duration = (end_tick - start_tick) as f32 * duration_per_tick;
-        header.write(&mut out_stream).unwrap();
+/// Cut multiple tick ranges from the demo, returning the cut demo together with a report of what was cut
+pub fn try_cut_with_report(
+    input: &[u8],
+    ranges: &[TickRange],
+    options: &CutOptions,
+) -> Result<(Vec<u8>, CutReport), CutError> {
+    let sink = Cursor::new(Vec::with_capacity(input.len()));
+    let (sink, report) = try_cut_ranges_to_writer(input, sink, ranges, None, options)?;
+    Ok((sink.into_inner(), report))
+}
 
-        let mut packets = RawPacketStream::new(stream.clone());
-        let mut start_handler = DemoHandler::default();
-        start_handler.handle_header(&header);
+/// Cut the demo to the given tick range, `options` is an object with the fields of [`CutOptions`]
+/// in camelCase, omitted fields use the default
+#[wasm_bindgen(js_name = cutWithOptions)]
+pub fn cut_with_options_js(
+    input: &[u8],
+    start_tick: u32,
+    end_tick: u32,
+    options: JsValue,
+) -> Result<Vec<u8>, JsValue> {
+    set_panic_hook();
+    let options = js_options(options)?;
+    try_cut_with_options(input, start_tick, end_tick, &options).map_err(js_error)
+}
 
-        let mut handler = DemoHandler::default();
-        handler.handle_header(&header);
+/// The result of a cut made from js, with the cut demo and the report as js object
+#[wasm_bindgen(js_name = CutResult)]
+pub struct CutResultJs {
+    data: Vec<u8>,
+    report: CutReport,
+}
 
-        let start_state = skip_start(&mut start_handler, &mut packets, start_tick);
+#[wasm_bindgen(js_class = CutResult)]
+impl CutResultJs {
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
 
-        for packet in start_state.start_packets {
-            packet
-                .encode(&mut out_stream, &handler.state_handler)
-                .unwrap();
-            handler.handle_packet(packet).unwrap();
-        }
-        let delta_tick = start_state.last_delta;
+    #[wasm_bindgen(getter)]
+    pub fn report(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.report).map_err(js_error)
+    }
+}
 
-        let start_entities = start_state.entities.entity_ids();
+/// Cut the demo to the given tick range, returning the cut demo with a report of what was cut
+///
+/// `options` is an object with the fields of [`CutOptions`] in camelCase, or undefined for the defaults
+#[wasm_bindgen(js_name = cutWithReport)]
+pub fn cut_with_report_js(
+    input: &[u8],
+    start_tick: u32,
+    end_tick: u32,
+    options: JsValue,
+) -> Result<CutResultJs, JsValue> {
+    set_panic_hook();
+    let options = js_options(options)?;
+    let ranges = [TickRange::new(start_tick, end_tick)];
+    let (data, report) = try_cut_with_report(input, &ranges, &options).map_err(js_error)?;
+    Ok(CutResultJs { data, report })
+}
 
-        let string_table_updates = start_state
-            .table_updates
-            .encode()
-            .into_iter()
-            .map(|msg| Message::UpdateStringTable(msg));
-        let (baseline_updates, entity_update, removed_update) = start_state
-            .entities
-            .encode(&start_handler.state_handler, delta_tick - 2);
-        let baseline_updates = baseline_updates.into_iter().map(Message::PacketEntities);
-        let start_packets = string_table_updates
-            .chain(baseline_updates)
-            .map(|msg| msg_packet(vec![net_tick(delta_tick - 2), msg]))
-            .chain(once(msg_packet(vec![
-                net_tick(delta_tick - 1),
-                Message::PacketEntities(entity_update),
-            ])))
-            .chain(once(Packet::Message(MessagePacket {
-                messages: vec![
-                    net_tick(delta_tick),
-                    Message::PacketEntities(removed_update),
-                ],
-                ..MessagePacket::default()
-            })));
-        for packet in start_packets {
-            packet
-                .encode(&mut out_stream, &handler.state_handler)
-                .unwrap();
-            handler.handle_packet(packet).unwrap();
-        }
+/// Cut the demo to the given tick range while reporting the progress to `progress`
+///
+/// `progress` is called with an object containing `bytes`, `totalBytes`, `tick` and `totalTicks`,
+/// returning `false` from it cancels the cut
+#[wasm_bindgen(js_name = cutWithProgress)]
+pub fn cut_with_progress_js(
+    input: &[u8],
+    start_tick: u32,
+    end_tick: u32,
+    options: JsValue,
+    progress: js_sys::Function,
+) -> Result<CutResultJs, JsValue> {
+    set_panic_hook();
+    let options = js_options(options)?;
+    let ranges = [TickRange::new(start_tick, end_tick)];
+    let sink = Cursor::new(Vec::with_capacity(input.len()));
+    let (sink, report) =
+        try_cut_ranges_with_progress(input, sink, &ranges, None, &options, js_progress(&progress))
+            .map_err(js_error)?;
+    Ok(CutResultJs {
+        data: sink.into_inner(),
+        report,
+    })
+}
 
-        // create the net ticks needed for later deltas
-        let fill_ticks = ((delta_tick + 1)..=start_state.server_tick)
-            .into_iter()
-            .map(|tick| net_tick(tick));
-        let fill_packets = fill_ticks.map(|msg| {
-            Packet::Message(MessagePacket {
-                messages: vec![
-                    msg,
-                    Message::PacketEntities(PacketEntitiesMessage {
-                        max_entries: start_state.entity_max,
-                        delta: Some((delta_tick - 1).try_into().unwrap()),
-                        ..PacketEntitiesMessage::default()
-                    }),
-                ],
-                ..MessagePacket::default()
-            })
-        });
-        for packet in fill_packets {
-            packet
-                .encode(&mut out_stream, &handler.state_handler)
-                .unwrap();
+/// Adapt a js progress callback, the operation is cancelled if the callback returns `false` or throws
+fn js_progress(callback: &js_sys::Function) -> impl FnMut(Progress) -> ControlFlow<()> + '_ {
+    move |progress| {
+        let progress = match JsValue::from_serde(&progress) {
+            Ok(progress) => progress,
+            Err(_) => return ControlFlow::Continue(()),
+        };
+        match callback.call1(&JsValue::NULL, &progress) {
+            Ok(result) if result.as_bool() == Some(false) => ControlFlow::Break(()),
+            Ok(_) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
         }
+    }
+}
+
+fn js_options(options: JsValue) -> Result<CutOptions, JsValue> {
+    if options.is_undefined() || options.is_null() {
+        Ok(CutOptions::default())
+    } else {
+        options.into_serde().map_err(js_error)
+    }
+}
+
+/// Cut the demo to the given time range, using the tick interval from the demo
+pub fn try_cut_time(input: &[u8], start: Duration, end: Duration) -> Result<Vec<u8>, CutError> {
+    try_cut_points(input, CutPoint::Time(start), CutPoint::Time(end))
+}
+
+/// Cut the demo between two points given as either tick or time
+pub fn try_cut_points(input: &[u8], start: CutPoint, end: CutPoint) -> Result<Vec<u8>, CutError> {
+    let interval = tick_interval(input)?;
+    try_cut(input, start.to_tick(interval), end.to_tick(interval))
+}
+
+/// Cut the demo between two points, given as either tick number or a time like "HH:MM:SS.fff"
+#[wasm_bindgen(js_name = cutTime)]
+pub fn cut_time_js(input: &[u8], start: &str, end: &str) -> Result<Vec<u8>, JsValue> {
+    set_panic_hook();
+    let start: CutPoint = start.parse().map_err(js_error)?;
+    let end: CutPoint = end.parse().map_err(js_error)?;
+    try_cut_points(input, start, end).map_err(js_error)
+}
+
+/// Get the tick interval of the demo in seconds
+///
+/// The interval is calculated from the header if possible, otherwise the interval from the `ServerInfo` message is used
+pub fn tick_interval(input: &[u8]) -> Result<f32, CutError> {
+    let demo = Demo::new(&input);
+    let mut stream = demo.get_stream();
+    let header = read_header(&mut stream)?;
+    if let Some(interval) = header_tick_interval(&header) {
+        return Ok(interval);
+    }
 
-        let mut mutators = MutatorList::new();
-        mutators.push_message_filter(|message: &Message| {
-            if let Message::UserMessage(usr_message) = message {
-                UserMessageType::CloseCaption != usr_message.message_type()
-            } else {
-                true
+    let mut handler = DemoHandler::default();
+    handler.handle_header(&header);
+    let mut packets = PacketReader::new(stream);
+    while let Some(packet) = packets.next(&handler.state_handler)? {
+        if let Packet::Signon(message_packet) | Packet::Message(message_packet) = &packet {
+            for message in &message_packet.messages {
+                if let Message::ServerInfo(info) = message {
+                    return Ok(info.interval_per_tick);
+                }
             }
-        });
-        mutators.push_message_mutator(DeleteFilter::new(start_entities, start_state.server_tick));
-        mutators.push_packet_mutator(move |packet: &mut Packet| {
-            packet.set_tick(packet.tick() - start_tick)
-        });
+        }
+        handler
+            .handle_packet(packet)
+            .map_err(CutError::state(packets.position()))?;
+    }
+    Ok(DEFAULT_TICK_INTERVAL)
+}
 
-        while let Some(mut packet) = packets.next(&handler.state_handler).unwrap() {
+#[wasm_bindgen(js_name = tickInterval)]
+pub fn tick_interval_js(input: &[u8]) -> Result<f32, JsValue> {
+    tick_interval(input).map_err(js_error)
+}
+
+/// Cut multiple tick ranges from the demo and splice them into a single continuous demo
+///
+/// Panics if the demo can't be parsed, see [`try_cut_ranges`] for a fallible version
+pub fn cut_ranges(input: &[u8], ranges: &[TickRange]) -> Vec<u8> {
+    try_cut_ranges(input, ranges).unwrap()
+}
+
+/// Cut multiple tick ranges, passed as a flat list of `start, end` pairs
+#[wasm_bindgen(js_name = cutRanges)]
+pub fn cut_ranges_js(input: &[u8], ticks: Vec<u32>) -> Result<Vec<u8>, JsValue> {
+    set_panic_hook();
+    let ranges = ticks
+        .chunks_exact(2)
+        .map(|pair| TickRange::new(pair[0], pair[1]))
+        .collect::<Vec<_>>();
+    try_cut_ranges(input, &ranges).map_err(js_error)
+}
+
+pub fn try_cut_ranges(input: &[u8], ranges: &[TickRange]) -> Result<Vec<u8>, CutError> {
+    Ok(try_cut_with_report(input, ranges, &CutOptions::default())?.0)
+}
+
+/// Cut the demo to the given tick range, using the index to skip to the start of the range
+///
+/// The index is ignored if it wasn't build for the input
+pub fn try_cut_with_index(
+    input: &[u8],
+    index: &DemoIndex,
+    start_tick: u32,
+    end_tick: u32,
+) -> Result<Vec<u8>, CutError> {
+    try_cut_ranges_with_index(input, index, &[TickRange::new(start_tick, end_tick)])
+}
+
+/// Cut multiple tick ranges from the demo, using the index to skip to the start of each range
+///
+/// The index is ignored if it wasn't build for the input
+pub fn try_cut_ranges_with_index(
+    input: &[u8],
+    index: &DemoIndex,
+    ranges: &[TickRange],
+) -> Result<Vec<u8>, CutError> {
+    let sink = Cursor::new(Vec::with_capacity(input.len()));
+    let (sink, _) =
+        try_cut_ranges_to_writer(input, sink, ranges, Some(index), &CutOptions::default())?;
+    Ok(sink.into_inner())
+}
+
+/// Cut the demo and stream the cut demo to `writer`
+///
+/// `input` can be a memory mapped demo (see [`map_demo`]) to cut a demo without loading it into memory.
+/// The writer doesn't need to support seeking, see [`try_cut_ranges_to_stream`] for how the header is written.
+pub fn cut_to_writer<W: Write>(
+    input: &[u8],
+    writer: W,
+    range: TickRange,
+) -> Result<(W, CutReport), CutError> {
+    try_cut_ranges_to_stream(input, writer, &[range], None, &CutOptions::default())
+}
+
+/// Cut a demo file and stream the cut demo to `writer`
+///
+/// The demo file is memory mapped instead of being read into memory
+#[cfg(not(target_arch = "wasm32"))]
+pub fn cut_file_to_writer<W: Write>(
+    path: &Path,
+    writer: W,
+    range: TickRange,
+) -> Result<(W, CutReport), CutError> {
+    let input = map_demo(path).map_err(CutError::Input)?;
+    cut_to_writer(&input, writer, range)
+}
+
+/// Memory map a demo file
+///
+/// The returned map can be passed to any of the cut functions as input.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn map_demo(path: &Path) -> std::io::Result<memmap2::Mmap> {
+    let file = std::fs::File::open(path)?;
+    // Safety: the map is only read from, if the demo is modified while it's mapped
+    // the cut will see garbage and fail to parse, the same as for any other corrupt input
+    unsafe { memmap2::Mmap::map(&file) }
+}
+
+/// Cut multiple tick ranges from the demo and stream the output to `writer`
+///
+/// The header is written to the start of the output once the demo is finished, by seeking back to the
+/// position the writer was at when the cut started.
+/// If an index is provided that was build for the input, it's used to skip to the start of each range
+pub fn try_cut_ranges_to_writer<W: Write + Seek>(
+    input: &[u8],
+    writer: W,
+    ranges: &[TickRange],
+    index: Option<&DemoIndex>,
+    options: &CutOptions,
+) -> Result<(W, CutReport), CutError> {
+    try_cut_ranges_with_progress(input, writer, ranges, index, options, |_| {
+        ControlFlow::Continue(())
+    })
+}
+
+/// Cut multiple tick ranges from the demo and stream the output to a writer that can't seek, like stdout
+///
+/// The header contains totals that are only known once the demo is written, without seeking back to
+/// the header the cut is made twice: once to calculate the header and once to write the demo.
+/// This keeps the memory use independent of the size of the demo, at the cost of taking twice as long.
+pub fn try_cut_ranges_to_stream<W: Write>(
+    input: &[u8],
+    writer: W,
+    ranges: &[TickRange],
+    index: Option<&DemoIndex>,
+    options: &CutOptions,
+) -> Result<(W, CutReport), CutError> {
+    let (_, _, header) = cut_ranges_to_sink(input, io::sink(), ranges, index, options, None, None)?;
+    let (writer, report, _) =
+        cut_ranges_to_sink(input, writer, ranges, index, options, Some(&header), None)?;
+    Ok((writer, report))
+}
+
+/// Cut multiple tick ranges from the demo and stream the output to `writer`, reporting the progress of the cut
+///
+/// `progress` is called every [`PROGRESS_INTERVAL`] bytes of the source demo and once the cut is finished,
+/// returning [`ControlFlow::Break`] cancels the cut with [`CutError::Cancelled`]
+pub fn try_cut_ranges_with_progress<W: Write + Seek, F: FnMut(Progress) -> ControlFlow<()>>(
+    input: &[u8],
+    mut writer: W,
+    ranges: &[TickRange],
+    index: Option<&DemoIndex>,
+    options: &CutOptions,
+    mut progress: F,
+) -> Result<(W, CutReport), CutError> {
+    let start = writer.stream_position().map_err(|source| CutError::Write {
+        position: PacketPosition::default(),
+        source,
+    })?;
+    let (mut writer, report, header) = cut_ranges_to_sink(
+        input,
+        writer,
+        ranges,
+        index,
+        options,
+        None,
+        Some(&mut progress),
+    )?;
+    rewrite_header(&mut writer, start, &header)
+        .map_err(CutError::write(PacketPosition::default()))?;
+    Ok((writer, report))
+}
+
+/// Cut multiple tick ranges from the demo and write them to `writer`
+///
+/// The output starts with `output_header` if given, or the header of the input otherwise,
+/// the header for the written demo is returned with the writer and report
+fn cut_ranges_to_sink<'a, W: Write>(
+    input: &'a [u8],
+    writer: W,
+    ranges: &[TickRange],
+    index: Option<&'a DemoIndex>,
+    options: &CutOptions,
+    output_header: Option<&Header>,
+    progress: Option<&mut dyn FnMut(Progress) -> ControlFlow<()>>,
+) -> Result<(W, CutReport, Header), CutError> {
+    let index = index.filter(|index| index.matches(input));
+    let demo = Demo::new(&input);
+    let mut stream = demo.get_stream();
+    let header = read_header(&mut stream)?;
+
+    let requested = ranges;
+    let ranges = normalize_ranges(ranges, header.ticks);
+    if ranges.is_empty() {
+        return Err(CutError::EmptyRange);
+    }
+    let mut effective_ranges = Vec::with_capacity(ranges.len());
+    let mut clamped = requested
+        .iter()
+        .any(|range| clamp_range(*range, header.ticks) != *range);
+
+    let mut output = CutOutput::new(output_header.unwrap_or(&header), writer, options)?;
+    let mut progress = match progress {
+        Some(callback) => ProgressTracker::new(callback, input.len(), header.ticks),
+        None => ProgressTracker::disabled(),
+    };
+
+    let mut packets = PacketReader::new(stream.clone());
+    let mut start_handler = DemoHandler::default();
+    start_handler.handle_header(&header);
+
+    let mut start_state = StartState::default();
+
+    for range in ranges {
+        let keyframe = index
+            .and_then(|index| index.keyframe_before(range.start))
+            .filter(|keyframe| keyframe.position.tick > packets.position().tick);
+        if let Some(keyframe) = keyframe {
+            keyframe.restore(
+                &header,
+                stream.clone(),
+                &mut start_handler,
+                &mut packets,
+                &mut start_state,
+            )?;
+        }
+        skip_start(
+            &mut start_handler,
+            &mut packets,
+            &mut start_state,
+            range.start,
+            &mut progress,
+        )?;
+        if packets.is_ended() {
+            // the demo ended before the range, which can happen if the header is wrong
+            clamped = true;
+            break;
+        }
+        output.start_segment(
+            range,
+            &start_state,
+            &start_handler.state_handler,
+            packets.position(),
+        )?;
+
+        let mut last_tick = packets.position().tick;
+        while let Some(packet) = packets.next(&start_handler.state_handler)? {
             let original_tick = packet.tick();
+            let position = packets.position();
 
-            mutators.mutate_packet(&mut packet);
+            start_state.handle_packet(&packet, &start_handler.state_handler);
+            output.write_packet(&packet, position)?;
+            start_handler
+                .handle_packet(packet)
+                .map_err(CutError::state(position))?;
+            progress.update(&packets)?;
 
-            if packet.packet_type() != PacketType::ConsoleCmd {
-                packet
-                    .encode(&mut out_stream, &handler.state_handler)
-                    .unwrap();
+            last_tick = max(last_tick, original_tick);
+            if original_tick >= range.end {
+                break;
             }
-            handler.handle_packet(packet).unwrap();
+        }
+
+        let effective = TickRange::new(range.start, min(range.end, last_tick));
+        if effective.end < range.end {
+            clamped = true;
+        }
+        output.end_segment(effective, &start_state);
+        effective_ranges.push(effective);
+    }
+
+    if effective_ranges.is_empty() {
+        return Err(CutError::EmptyRange);
+    }
+
+    let (writer, report, header) = output.finish(packets.position())?;
+    progress.finish(&packets)?;
+    Ok((
+        writer,
+        CutReport {
+            ranges: effective_ranges,
+            clamped,
+            ..report
+        },
+        header,
+    ))
+}
+
+/// Cut multiple clips from the demo in a single pass
+///
+/// Unlike [`try_cut_ranges`] which splices all ranges into one demo, every range results in a separate demo.
+/// The outputs are returned in the same order as the ranges.
+pub fn try_cut_batch(input: &[u8], ranges: &[TickRange]) -> Result<Vec<Vec<u8>>, CutError> {
+    let mut outputs = vec![Vec::new(); ranges.len()];
+    try_cut_batch_with(input, ranges, &CutOptions::default(), |clip, output| {
+        outputs[clip] = output
+    })?;
+    Ok(outputs)
+}
+
+/// Cut multiple clips from the demo in a single pass, passing every clip to `on_clip` as soon as it's finished
+///
+/// `on_clip` receives the index of the range and the cut demo
+pub fn try_cut_batch_with<F: FnMut(usize, Vec<u8>)>(
+    input: &[u8],
+    ranges: &[TickRange],
+    options: &CutOptions,
+    mut on_clip: F,
+) -> Result<(), CutError> {
+    let demo = Demo::new(&input);
+    let mut stream = demo.get_stream();
+    let header = read_header(&mut stream)?;
+
+    let ranges = ranges
+        .iter()
+        .map(|range| clamp_range(*range, header.ticks))
+        .collect::<Vec<_>>();
+    if ranges.iter().any(TickRange::is_empty) {
+        return Err(CutError::EmptyRange);
+    }
+
+    let mut pending = (0..ranges.len()).collect::<Vec<_>>();
+    // sorted in reverse so we can pop the next clip to start
+    pending.sort_by_key(|clip| std::cmp::Reverse(ranges[*clip].start));
+    let mut active: Vec<(usize, CutOutput<Cursor<Vec<u8>>>)> = Vec::new();
+
+    let mut packets = PacketReader::new(stream);
+    let mut handler = DemoHandler::default();
+    handler.handle_header(&header);
+    let mut start_state = StartState::default();
+
+    while let Some(packet) = packets.next(&handler.state_handler)? {
+        let position = packets.position();
+        let tick = packet.tick();
+        let packet_type = packet.packet_type();
+
+        if PRESERVE_PACKETS.contains(&packet_type) {
+            start_state.start_packets.push(packet.clone());
+            handler
+                .handle_packet(packet)
+                .map_err(CutError::state(position))?;
+            continue;
+        }
 
-            if original_tick >= end_tick {
+        start_state.handle_packet(&packet, &handler.state_handler);
+        for (_, output) in active.iter_mut() {
+            output.write_packet(&packet, position)?;
+        }
+        handler
+            .handle_packet(packet)
+            .map_err(CutError::state(position))?;
+
+        let (finished, still_active): (Vec<_>, Vec<_>) = take(&mut active)
+            .into_iter()
+            .partition(|(clip, _)| tick >= ranges[*clip].end);
+        active = still_active;
+        for (clip, mut output) in finished {
+            output.end_segment(ranges[clip], &start_state);
+            on_clip(clip, finish_clip(output, position)?);
+        }
+
+        if packet_type == PacketType::ConsoleCmd {
+            continue;
+        }
+        while let Some(&clip) = pending.last() {
+            let range = ranges[clip];
+            if range.start > tick {
                 break;
             }
+            pending.pop();
+            let capacity = (input.len() / header.ticks.max(1) as usize)
+                .saturating_mul(range.len() as usize)
+                .min(input.len());
+            let mut output =
+                CutOutput::new(&header, Cursor::new(Vec::with_capacity(capacity)), options)?;
+            output.start_segment(range, &start_state, &handler.state_handler, position)?;
+            active.push((clip, output));
         }
-        PacketType::Stop.write(&mut out_stream).unwrap();
-        StopPacket {
-            tick: end_tick - start_tick,
+    }
+
+    let position = packets.position();
+    for (clip, mut output) in active {
+        output.end_segment(ranges[clip], &start_state);
+        on_clip(clip, finish_clip(output, position)?);
+    }
+    Ok(())
+}
+
+/// Finish a clip of a batch cut, writing the final header to the start of the clip
+fn finish_clip(
+    output: CutOutput<Cursor<Vec<u8>>>,
+    position: PacketPosition,
+) -> Result<Vec<u8>, CutError> {
+    let (mut sink, _, header) = output.finish(position)?;
+    rewrite_header(&mut sink, 0, &header).map_err(CutError::write(position))?;
+    Ok(sink.into_inner())
+}
+
+/// Ranges closer together than this are merged, to leave room for the server ticks
+/// needed to rebuild the state at the start of the next segment
+const MIN_SEGMENT_GAP: u32 = 10;
+
+/// Clamp the range to the length of the demo, leaving at least a few ticks to cut if possible
+///
+/// Demos that are still being recorded have no tick count in the header, those ranges are left as is
+/// and end wherever the demo ends.
+fn clamp_range(range: TickRange, demo_ticks: u32) -> TickRange {
+    if demo_ticks == 0 {
+        return range;
+    }
+    TickRange {
+        start: min(demo_ticks.saturating_sub(MIN_SEGMENT_GAP), range.start),
+        end: min(demo_ticks, range.end),
+    }
+}
+
+/// Clamp the ranges to the demo length and sort and merge them
+fn normalize_ranges(ranges: &[TickRange], demo_ticks: u32) -> Vec<TickRange> {
+    let mut ranges = ranges
+        .iter()
+        .map(|range| clamp_range(*range, demo_ticks))
+        .filter(|range| !range.is_empty())
+        .collect::<Vec<_>>();
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<TickRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(MIN_SEGMENT_GAP) => {
+                last.end = max(last.end, range.end);
+            }
+            _ => merged.push(range),
         }
-        .encode(&mut out_stream, &handler.state_handler)
-        .unwrap();
     }
-    out_buffer
+    merged
 }
 
+#[derive(Default)]
 struct StartState<'a> {
     entities: ActiveEntities,
     table_updates: StringTablesUpdates,
@@ -177,45 +737,152 @@ struct StartState<'a> {
     server_tick: u32,
     entity_max: u16,
     last_delta: u32,
+    net_timings: NetTimings,
 }
 
-fn skip_start<'a>(
+impl<'a> StartState<'a> {
+    fn handle_packet(&mut self, packet: &Packet<'a>, state: &ParserState) {
+        if let Packet::Message(message_packet) = packet {
+            for msg in &message_packet.messages {
+                self.table_updates.handle_message(&msg);
+                match msg {
+                    Message::PacketEntities(msg) => {
+                        if let Some(delta) = msg.delta {
+                            self.last_delta = delta.get();
+                        }
+                        self.entity_max = msg.max_entries;
+                        self.entities.handle_message(msg, state);
+                    }
+                    Message::NetTick(net_tick) => {
+                        self.server_tick = net_tick.tick;
+                        self.net_timings.push(net_tick);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+impl<'a> StartState<'a> {
+    /// The tick interval from the server info in the start packets
+    fn tick_interval(&self) -> f32 {
+        self.start_packets
+            .iter()
+            .filter_map(|packet| match packet {
+                Packet::Signon(message_packet) => Some(message_packet),
+                _ => None,
+            })
+            .flat_map(|message_packet| message_packet.messages.iter())
+            .find_map(|message| match message {
+                Message::ServerInfo(info) => Some(info.interval_per_tick),
+                _ => None,
+            })
+            .unwrap_or(DEFAULT_TICK_INTERVAL)
+    }
+
+    /// Create a net tick for a synthesized packet, with the timing of the source demo around that tick
+    fn net_tick(&self, tick: u32) -> Message<'static> {
+        self.net_timings.net_tick(tick, self.tick_interval())
+    }
+}
+
+/// Number of recent net ticks to remember the timing of
+const NET_TIMING_HISTORY: usize = 256;
+
+/// Net tick scale used by the engine for the frame time and deviation
+const NET_TICK_SCALE: f32 = 100_000.0;
+
+/// Timing of the most recent net ticks in the source demo, as `(tick, frame_time, std_dev)`
+#[derive(Default, Clone)]
+struct NetTimings {
+    recent: VecDeque<(u32, u16, u16)>,
+}
+
+impl NetTimings {
+    fn push(&mut self, net_tick: &NetTickMessage) {
+        if self.recent.len() == NET_TIMING_HISTORY {
+            self.recent.pop_front();
+        }
+        self.recent
+            .push_back((net_tick.tick, net_tick.frame_time, net_tick.std_dev));
+    }
+
+    /// Get a net tick for `tick`, interpolating between the nearest net ticks of the source demo
+    ///
+    /// If no net ticks have been seen, the frame time is derived from the tick interval
+    fn net_tick(&self, tick: u32, interval: f32) -> Message<'static> {
+        let after = self.recent.partition_point(|(seen, _, _)| *seen < tick);
+        let (frame_time, std_dev) = match (
+            after
+                .checked_sub(1)
+                .and_then(|index| self.recent.get(index)),
+            self.recent.get(after),
+        ) {
+            (_, Some((seen, frame_time, std_dev))) if *seen == tick => (*frame_time, *std_dev),
+            (Some(before), Some(after)) => {
+                let progress = (tick - before.0) as f32 / (after.0 - before.0) as f32;
+                let lerp =
+                    |a: u16, b: u16| (a as f32 + (b as f32 - a as f32) * progress).round() as u16;
+                (lerp(before.1, after.1), lerp(before.2, after.2))
+            }
+            (Some((_, frame_time, std_dev)), None) | (None, Some((_, frame_time, std_dev))) => {
+                (*frame_time, *std_dev)
+            }
+            (None, None) => ((interval * NET_TICK_SCALE).round() as u16, 0),
+        };
+        Message::NetTick(NetTickMessage {
+            tick,
+            frame_time,
+            std_dev,
+        })
+    }
+}
+
+fn read_header(stream: &mut Stream) -> Result<Header, CutError> {
+    Header::read(stream).map_err(|e| CutError::Header(ParseError::from(e)))
+}
+
+/// Read the packets at the start of the demo that are preserved in the output
+fn read_start_packets<'a>(
     handler: &mut DemoHandler<'a, NullHandler>,
-    packets: &mut RawPacketStream<'a>,
-    start_tick: u32,
-) -> StartState<'a> {
-    let mut entities = ActiveEntities::default();
-    let mut table_updates = StringTablesUpdates::default();
+    packets: &mut PacketReader<'a>,
+) -> Result<Vec<Packet<'a>>, CutError> {
     let mut start_packets = Vec::with_capacity(6);
-    let mut server_tick = 0;
-    let mut entity_max = 0;
-    let mut last_delta = 0;
+    while let Some(packet) = packets.next(&handler.state_handler)? {
+        if !PRESERVE_PACKETS.contains(&packet.packet_type()) {
+            break;
+        }
+        start_packets.push(packet.clone());
+        handler
+            .handle_packet(packet)
+            .map_err(CutError::state(packets.position()))?;
+    }
+    Ok(start_packets)
+}
 
-    while let Some(packet) = packets.next(&handler.state_handler).unwrap() {
+/// Process packets until `start_tick` is reached, keeping track of the state needed to start the output from there
+fn skip_start<'a>(
+    handler: &mut DemoHandler<'a, NullHandler>,
+    packets: &mut PacketReader<'a>,
+    start_state: &mut StartState<'a>,
+    start_tick: u32,
+    progress: &mut ProgressTracker,
+) -> Result<(), CutError> {
+    while let Some(packet) = packets.next(&handler.state_handler)? {
+        progress.update(packets)?;
+        let position = packets.position();
         if PRESERVE_PACKETS.contains(&packet.packet_type()) {
-            start_packets.push(packet.clone());
-            handler.handle_packet(packet).unwrap();
+            start_state.start_packets.push(packet.clone());
+            handler
+                .handle_packet(packet)
+                .map_err(CutError::state(position))?;
         } else if packet.packet_type() != PacketType::ConsoleCmd {
-            if let Packet::Message(message_packet) = &packet {
-                for msg in &message_packet.messages {
-                    table_updates.handle_message(&msg);
-                    match msg {
-                        Message::PacketEntities(msg) => {
-                            if let Some(delta) = msg.delta {
-                                last_delta = delta.get();
-                            }
-                            entity_max = msg.max_entries;
-                            entities.handle_message(msg, &handler.state_handler);
-                        }
-                        Message::NetTick(NetTickMessage { tick, .. }) => {
-                            server_tick = *tick;
-                        }
-                        _ => {}
-                    }
-                }
-            }
+            start_state.handle_packet(&packet, &handler.state_handler);
             let tick = packet.tick();
-            handler.handle_packet(packet).unwrap();
+            handler
+                .handle_packet(packet)
+                .map_err(CutError::state(position))?;
 
             if tick >= start_tick {
                 break;
@@ -223,14 +890,7 @@ fn skip_start<'a>(
         }
     }
 
-    StartState {
-        entities,
-        table_updates,
-        start_packets,
-        server_tick,
-        entity_max,
-        last_delta,
-    }
+    Ok(())
 }
 
 struct DeleteFilter {
@@ -268,24 +928,215 @@ impl MessageMutator for DeleteFilter {
     }
 }
 
-fn msg_packet(messages: Vec<Message>) -> Packet {
+/// Point entity deltas that reference a tick up to the server tick to the start state
+///
+/// The source demo contains a frame for every one of these ticks, while the output only contains the start state,
+/// which already includes every update up to the server tick.
+/// This also covers cuts that start before the first delta, where the start state is written at a later tick than the server tick.
+struct DeltaRewrite {
+    state_tick: u32,
+    server_tick: u32,
+}
+
+impl DeltaRewrite {
+    pub fn new(state_tick: u32, server_tick: u32) -> Self {
+        DeltaRewrite {
+            state_tick,
+            server_tick,
+        }
+    }
+}
+
+impl MessageMutator for DeltaRewrite {
+    fn mutate_message(&self, message: &mut Message) {
+        if let Message::PacketEntities(message) = message {
+            if let Some(delta) = message.delta {
+                if delta.get() <= max(self.server_tick, self.state_tick) {
+                    message.delta = NonZeroU32::new(self.state_tick);
+                }
+            }
+        }
+    }
+}
+
+fn msg_packet(tick: u32, messages: Vec<Message>) -> Packet {
     Packet::Message(MessagePacket {
+        tick,
         messages,
         ..MessagePacket::default()
     })
 }
 
-fn net_tick(tick: u32) -> Message<'static> {
-    Message::NetTick(NetTickMessage {
-        tick,
-        frame_time: 1881,
-        std_dev: 263,
+/// Find highlights in the demo
+///
+/// Panics if the demo can't be parsed, see [`try_bookmarks`] for a fallible version
+pub fn bookmarks(input: &[u8]) -> Bookmarks {
+    try_bookmarks(input).unwrap()
+}
+
+pub fn try_bookmarks(input: &[u8]) -> Result<Bookmarks, CutError> {
+    try_bookmarks_with_options(input, &HighlightOptions::default())
+}
+
+/// Find highlights in the demo, using custom settings for the highlight detection
+pub fn try_bookmarks_with_options(
+    input: &[u8],
+    options: &HighlightOptions,
+) -> Result<Bookmarks, CutError> {
+    let (_, bookmarks) = analyse(
+        input,
+        HighlightAnalyser::new(options.clone()),
+        &mut ProgressTracker::disabled(),
+    )?;
+    Ok(bookmarks)
+}
+
+/// Find highlights in the demo, reporting the progress of the scan
+///
+/// `progress` is called every [`PROGRESS_INTERVAL`] bytes of the demo and once the scan is finished,
+/// returning [`ControlFlow::Break`] cancels the scan with [`CutError::Cancelled`]
+pub fn try_bookmarks_with_progress<F: FnMut(Progress) -> ControlFlow<()>>(
+    input: &[u8],
+    options: &HighlightOptions,
+    mut progress: F,
+) -> Result<Bookmarks, CutError> {
+    let total_ticks = Header::read(&mut Demo::new(&input).get_stream())
+        .map(|header| header.ticks)
+        .unwrap_or_default();
+    let mut progress = ProgressTracker::new(&mut progress, input.len(), total_ticks);
+    let (_, bookmarks) = analyse(
+        input,
+        HighlightAnalyser::new(options.clone()),
+        &mut progress,
+    )?;
+    Ok(bookmarks)
+}
+
+/// A highlight as returned to js, with the range to cut for it
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HighlightJs<'a> {
+    tick: u32,
+    player: Option<&'a PlayerState>,
+    kind: &'static str,
+    source: &'a HighlightSource,
+    suggested_range: TickRange,
+}
+
+/// Find the highlights in the demo
+///
+/// Returns an array of objects with the `tick`, `player`, `kind`, the full `source` of the highlight
+/// and a `suggestedRange` to cut. `player` is null for highlights about a team or round.
+/// `options` is an object with the fields of [`HighlightOptions`] in camelCase, or undefined for the defaults
+#[wasm_bindgen(js_name = scanHighlights)]
+pub fn scan_highlights_js(input: &[u8], options: JsValue) -> Result<JsValue, JsValue> {
+    set_panic_hook();
+    let options = js_highlight_options(options)?;
+    let bookmarks = try_bookmarks_with_options(input, &options).map_err(js_error)?;
+    highlights_js(input, &bookmarks)
+}
+
+/// Find the highlights in the demo while reporting the progress to `progress`
+///
+/// `progress` is called the same as for `cutWithProgress`, returning `false` from it cancels the scan
+#[wasm_bindgen(js_name = scanHighlightsWithProgress)]
+pub fn scan_highlights_with_progress_js(
+    input: &[u8],
+    options: JsValue,
+    progress: js_sys::Function,
+) -> Result<JsValue, JsValue> {
+    set_panic_hook();
+    let options = js_highlight_options(options)?;
+    let bookmarks =
+        try_bookmarks_with_progress(input, &options, js_progress(&progress)).map_err(js_error)?;
+    highlights_js(input, &bookmarks)
+}
+
+fn js_highlight_options(options: JsValue) -> Result<HighlightOptions, JsValue> {
+    if options.is_undefined() || options.is_null() {
+        Ok(HighlightOptions::default())
+    } else {
+        options.into_serde().map_err(js_error)
+    }
+}
+
+fn highlights_js(input: &[u8], bookmarks: &Bookmarks) -> Result<JsValue, JsValue> {
+    let interval = tick_interval(input).map_err(js_error)?;
+    let highlights = bookmarks
+        .highlights
+        .iter()
+        .map(|highlight| HighlightJs {
+            tick: highlight.tick,
+            player: highlight.player.as_ref(),
+            kind: highlight.source.kind(),
+            source: &highlight.source,
+            suggested_range: highlight.suggested_range(interval),
+        })
+        .collect::<Vec<_>>();
+    JsValue::from_serde(&highlights).map_err(js_error)
+}
+
+/// Get the map, server, duration, players and rounds of a demo
+///
+/// Panics if the demo can't be parsed, see [`try_demo_info`] for a fallible version
+pub fn demo_info(input: &[u8]) -> DemoInfo {
+    try_demo_info(input).unwrap()
+}
+
+pub fn try_demo_info(input: &[u8]) -> Result<DemoInfo, CutError> {
+    let (header, output) = analyse(
+        input,
+        InfoAnalyser::default(),
+        &mut ProgressTracker::disabled(),
+    )?;
+    let tick_interval = header_tick_interval(&header)
+        .or(output.interval)
+        .unwrap_or(DEFAULT_TICK_INTERVAL);
+    // demos that are still being recorded don't have their length in the header yet
+    let ticks = if header.ticks > 0 {
+        header.ticks
+    } else {
+        output.last_tick
+    };
+    Ok(DemoInfo {
+        map: header.map,
+        server: header.server,
+        nick: header.nick,
+        duration: ticks_to_duration(ticks, tick_interval).as_secs_f32(),
+        ticks,
+        tick_interval,
+        players: output.players,
+        rounds: output.rounds,
     })
 }
 
-pub fn bookmarks(input: &[u8]) -> Vec<Highlight> {
+#[wasm_bindgen(js_name = demoInfo)]
+pub fn demo_info_js(input: &[u8]) -> Result<JsValue, JsValue> {
+    set_panic_hook();
+    let info = try_demo_info(input).map_err(js_error)?;
+    JsValue::from_serde(&info).map_err(js_error)
+}
+
+/// Run an analyser over all packets in the demo
+fn analyse<A: MessageHandler>(
+    input: &[u8],
+    analyser: A,
+    progress: &mut ProgressTracker,
+) -> Result<(Header, A::Output), CutError> {
     let demo = Demo::new(&input);
-    let parser = DemoParser::new_with_analyser(demo.get_stream(), HighlightAnalyser::default());
-    let (_, highlights) = parser.parse().unwrap();
-    highlights
+    let mut stream = demo.get_stream();
+    let header = read_header(&mut stream)?;
+
+    let mut handler = DemoHandler::with_analyser(analyser);
+    handler.handle_header(&header);
+
+    let mut packets = PacketReader::new(stream);
+    while let Some(packet) = packets.next(&handler.state_handler)? {
+        handler
+            .handle_packet(packet)
+            .map_err(CutError::state(packets.position()))?;
+        progress.update(&packets)?;
+    }
+    progress.finish(&packets)?;
+    Ok((header, handler.into_output()))
 }
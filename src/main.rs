@@ -0,0 +1,40 @@
+use crate::cli::CliError;
+use clap::{Parser, Subcommand};
+
+mod cli;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Cut one or more tick or time ranges from a demo
+    Cut(cli::cut::CutArgs),
+    /// List the highlights in a demo
+    Bookmarks(cli::bookmarks::BookmarksArgs),
+    /// Show information about a demo
+    Info(cli::info::InfoArgs),
+    /// Verify that a cut demo matches the original
+    Verify(cli::verify::VerifyArgs),
+    /// Split a demo into parts of equal length
+    Split(cli::split::SplitArgs),
+}
+
+fn main() {
+    let args = Args::parse();
+    let result: Result<(), CliError> = match args.command {
+        Command::Cut(args) => cli::cut::run(args),
+        Command::Bookmarks(args) => cli::bookmarks::run(args),
+        Command::Info(args) => cli::info::run(args),
+        Command::Verify(args) => cli::verify::run(args),
+        Command::Split(args) => cli::split::run(args),
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(e.exit_code());
+    }
+}
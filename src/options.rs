@@ -0,0 +1,113 @@
+use crate::mutate::MutatorList;
+use serde::de::value::{Error as ValueError, StrDeserializer};
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::message::usermessage::UserMessageType;
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::MessageType;
+use thiserror::Error;
+
+/// Messages that are needed to keep the output playable and are never dropped
+const REQUIRED_MESSAGES: &[MessageType] = &[
+    MessageType::NetTick,
+    MessageType::SignOnState,
+    MessageType::ServerInfo,
+    MessageType::ClassInfo,
+    MessageType::CreateStringTable,
+    MessageType::UpdateStringTable,
+    MessageType::SetView,
+    MessageType::PacketEntities,
+];
+
+/// Options for which parts of the source demo are copied into the cut
+///
+/// The defaults drop close captions and console commands, and keep everything else.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CutOptions {
+    /// Which message types to copy, messages needed for playback are always kept
+    pub messages: TypeFilter<MessageType>,
+    /// Which user message types to copy
+    pub user_messages: TypeFilter<UserMessageType>,
+    /// Whether to copy the console command packets
+    pub keep_console_commands: bool,
+    /// Whether to copy voice data
+    pub keep_voice: bool,
+    /// Keep the tick numbers of the source demo instead of starting the output at tick 0
+    ///
+    /// The output then covers ticks 0 to the end of the last range, with the packets before the first range left out.
+    pub preserve_ticks: bool,
+}
+
+impl Default for CutOptions {
+    fn default() -> Self {
+        CutOptions {
+            messages: TypeFilter::default(),
+            user_messages: TypeFilter::Drop(vec![UserMessageType::CloseCaption]),
+            keep_console_commands: false,
+            keep_voice: true,
+            preserve_ticks: false,
+        }
+    }
+}
+
+/// Either a list of types to drop, or a list of the only types to keep
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TypeFilter<T> {
+    Drop(Vec<T>),
+    Keep(Vec<T>),
+}
+
+impl<T> Default for TypeFilter<T> {
+    fn default() -> Self {
+        TypeFilter::Drop(Vec::new())
+    }
+}
+
+impl<T: PartialEq> TypeFilter<T> {
+    pub fn allows(&self, ty: &T) -> bool {
+        match self {
+            TypeFilter::Drop(types) => !types.contains(ty),
+            TypeFilter::Keep(types) => types.contains(ty),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("unknown message type \"{name}\"")]
+pub struct UnknownTypeError {
+    pub name: String,
+}
+
+/// Parse a message or user message type from its name, e.g. "VoiceData" or "CloseCaption"
+pub fn parse_type_name<T: DeserializeOwned>(name: &str) -> Result<T, UnknownTypeError> {
+    let deserializer: StrDeserializer<ValueError> = name.into_deserializer();
+    T::deserialize(deserializer).map_err(|_| UnknownTypeError {
+        name: name.to_string(),
+    })
+}
+
+impl CutOptions {
+    /// Add the message filters for these options to the mutator list
+    pub(crate) fn push_filters(&self, mutators: &mut MutatorList) {
+        let messages = self.messages.clone();
+        let user_messages = self.user_messages.clone();
+        let keep_voice = self.keep_voice;
+        mutators.push_message_filter(move |message: &Message| {
+            let message_type = message.get_message_type();
+            if REQUIRED_MESSAGES.contains(&message_type) {
+                return true;
+            }
+            if !keep_voice && message_type == MessageType::VoiceData {
+                return false;
+            }
+            if let Message::UserMessage(usr_message) = message {
+                if !user_messages.allows(&usr_message.message_type()) {
+                    return false;
+                }
+            }
+            messages.allows(&message_type)
+        });
+    }
+}
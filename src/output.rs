@@ -0,0 +1,187 @@
+use crate::entity::ActiveEntities;
+use crate::error::{CutError, PacketPosition};
+use crate::mutate::{MutatorList, PacketMutator};
+use crate::options::CutOptions;
+use crate::writer::DemoWriter;
+use crate::{msg_packet, CutReport, DeleteFilter, DeltaRewrite, StartState, TickRange};
+use std::io::Write;
+use std::iter::once;
+use tf_demo_parser::demo::header::Header;
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::demo::packet::{Packet, PacketType};
+use tf_demo_parser::demo::parser::{DemoHandler, NullHandler};
+use tf_demo_parser::ParserState;
+
+/// Earliest tick the start state can be written at, the baselines are written two ticks before the state
+const MIN_STATE_TICK: u32 = 2;
+
+/// A demo being written from one or more segments of the source demo
+pub struct CutOutput<'a, W: Write> {
+    writer: DemoWriter<W>,
+    handler: DemoHandler<'a, NullHandler>,
+    mutators: MutatorList,
+    options: CutOptions,
+    /// Whether the preserved start packets have been written
+    started: bool,
+    /// Tick in the output at which the current segment started
+    output_tick: u32,
+    /// The entities the output contains at the end of the previous segment
+    previous_entities: ActiveEntities,
+    report: CutReport,
+}
+
+impl<'a, W: Write> CutOutput<'a, W> {
+    pub fn new(header: &Header, sink: W, options: &CutOptions) -> Result<Self, CutError> {
+        let writer = DemoWriter::new(header.clone(), sink)
+            .map_err(CutError::write(PacketPosition::default()))?;
+        let mut handler = DemoHandler::default();
+        handler.handle_header(header);
+
+        Ok(CutOutput {
+            writer,
+            handler,
+            mutators: MutatorList::new(),
+            options: options.clone(),
+            started: false,
+            output_tick: 0,
+            previous_entities: ActiveEntities::default(),
+            report: CutReport::default(),
+        })
+    }
+
+    fn write_synthesized(
+        &mut self,
+        packet: Packet<'a>,
+        position: PacketPosition,
+    ) -> Result<(), CutError> {
+        self.writer
+            .write_packet(&packet, &self.handler.state_handler)
+            .map_err(CutError::write(position))?;
+        self.handler
+            .handle_packet(packet)
+            .map_err(CutError::state(position))
+    }
+
+    /// Start a new segment, writing the packets needed to bring the output to the state of the source demo
+    ///
+    /// `source_state` is the parser state of the source demo at `position`
+    pub fn start_segment(
+        &mut self,
+        range: TickRange,
+        start_state: &StartState<'a>,
+        source_state: &ParserState,
+        position: PacketPosition,
+    ) -> Result<(), CutError> {
+        if !self.started {
+            for packet in start_state.start_packets.iter().cloned() {
+                self.write_synthesized(packet, position)?;
+            }
+            self.started = true;
+        }
+        if self.options.preserve_ticks {
+            // ranges are sorted, so this never moves the output backwards
+            self.output_tick = range.start;
+        }
+        let output_tick = self.output_tick;
+        // without any delta seen yet, the cut starts before or at the first full update
+        // and the state is written at the earliest ticks that leave room for the baselines
+        let delta_tick = start_state.last_delta.max(MIN_STATE_TICK);
+
+        let start_entities = start_state.entities.entity_ids();
+
+        let string_table_updates = start_state
+            .table_updates
+            .clone()
+            .encode()
+            .into_iter()
+            .map(|msg| Message::UpdateStringTable(msg));
+        let (baseline_updates, entity_update, removed_update) = start_state
+            .entities
+            .clone()
+            .encode(source_state, delta_tick - 2, &self.previous_entities);
+        let baseline_updates = baseline_updates.into_iter().map(Message::PacketEntities);
+        let start_packets = string_table_updates
+            .chain(baseline_updates)
+            .map(|msg| msg_packet(output_tick, vec![start_state.net_tick(delta_tick - 2), msg]))
+            .chain(once(msg_packet(
+                output_tick,
+                vec![
+                    start_state.net_tick(delta_tick - 1),
+                    Message::PacketEntities(entity_update),
+                ],
+            )))
+            .chain(once(msg_packet(
+                output_tick,
+                vec![
+                    start_state.net_tick(delta_tick),
+                    Message::PacketEntities(removed_update),
+                ],
+            )));
+        for packet in start_packets {
+            self.write_synthesized(packet, position)?;
+            self.report.synthesized_packets += 1;
+        }
+        // the baselines, entity update and removed entities are each written at their own tick
+        self.report.synthesized_ticks += 3;
+        self.report.entities += start_entities.len() as u32;
+        self.report.string_table_entries += start_state.table_updates.entry_count() as u32;
+
+        let range_start = range.start;
+        let mut mutators = MutatorList::new();
+        self.options.push_filters(&mut mutators);
+        mutators.push_message_mutator(DeleteFilter::new(start_entities, start_state.server_tick));
+        // the output only contains the state at `delta_tick`, instead of every tick up to the server tick
+        mutators.push_message_mutator(DeltaRewrite::new(delta_tick, start_state.server_tick));
+        mutators.push_packet_mutator(move |packet: &mut Packet| {
+            packet.set_tick(packet.tick().saturating_sub(range_start) + output_tick)
+        });
+        self.mutators = mutators;
+
+        Ok(())
+    }
+
+    /// Write a packet from the source demo to the current segment
+    pub fn write_packet(
+        &mut self,
+        packet: &Packet<'a>,
+        position: PacketPosition,
+    ) -> Result<(), CutError> {
+        let mut packet = packet.clone();
+        self.mutators.mutate_packet(&mut packet);
+
+        if self.options.keep_console_commands || packet.packet_type() != PacketType::ConsoleCmd {
+            self.writer
+                .write_packet(&packet, &self.handler.state_handler)
+                .map_err(CutError::write(position))?;
+        }
+        self.handler
+            .handle_packet(packet)
+            .map_err(CutError::state(position))
+    }
+
+    pub fn end_segment(&mut self, range: TickRange, start_state: &StartState<'a>) {
+        self.output_tick += range.len();
+        self.previous_entities = start_state.entities.clone();
+    }
+
+    /// Finish the output, returning the sink, a report of what was written and the final header
+    ///
+    /// The ranges in the report are left for the caller to fill in, as is writing the final header
+    /// over the header written at the start
+    pub fn finish(self, position: PacketPosition) -> Result<(W, CutReport, Header), CutError> {
+        let packets_written = self.writer.packets_written();
+        let (sink, output_size, header) = self
+            .writer
+            .finish(self.output_tick, &self.handler.state_handler)
+            .map_err(CutError::write(position))?;
+        Ok((
+            sink,
+            CutReport {
+                packets_written,
+                output_size,
+                ..self.report
+            },
+            header,
+        ))
+    }
+}
@@ -0,0 +1,202 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tf_demo_parser::demo::data::UserInfo;
+use tf_demo_parser::demo::gameevent_gen::GameEvent;
+use tf_demo_parser::demo::message::packetentities::EntityId;
+use tf_demo_parser::demo::packet::stringtable::StringTableEntry;
+use tf_demo_parser::demo::parser::analyser::UserId;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DemoPlayer {
+    pub user_id: UserId,
+    pub name: String,
+    pub steam_id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Team {
+    Red,
+    Blue,
+}
+
+impl Team {
+    pub fn from_number(team: u16) -> Option<Self> {
+        match team {
+            2 => Some(Team::Red),
+            3 => Some(Team::Blue),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Class {
+    Scout,
+    Sniper,
+    Soldier,
+    Demoman,
+    Medic,
+    Heavy,
+    Pyro,
+    Spy,
+    Engineer,
+}
+
+impl Class {
+    pub fn from_number(class: u16) -> Option<Self> {
+        Some(match class {
+            1 => Class::Scout,
+            2 => Class::Sniper,
+            3 => Class::Soldier,
+            4 => Class::Demoman,
+            5 => Class::Medic,
+            6 => Class::Heavy,
+            7 => Class::Pyro,
+            8 => Class::Spy,
+            9 => Class::Engineer,
+            _ => return None,
+        })
+    }
+}
+
+/// A player with their team and class at a specific tick
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerState {
+    pub user_id: UserId,
+    pub name: String,
+    pub steam_id: String,
+    pub team: Option<Team>,
+    pub class: Option<Class>,
+}
+
+/// Parse the player info from an entry in the "userinfo" string table
+pub(crate) fn parse_user_info(entry: &StringTableEntry) -> Option<UserInfo> {
+    UserInfo::parse_from_string_table(
+        entry.text.as_ref().map(|s| s.as_ref()),
+        entry.extra_data.as_ref().map(|data| data.data.clone()),
+    )
+    .ok()
+    .flatten()
+}
+
+struct TrackedPlayer {
+    info: DemoPlayer,
+    entity: EntityId,
+    team: Option<Team>,
+    class: Option<Class>,
+}
+
+/// Keeps track of the players in the demo, with their current team and class
+#[derive(Default)]
+pub(crate) struct PlayerTracker {
+    players: BTreeMap<UserId, TrackedPlayer>,
+}
+
+impl PlayerTracker {
+    pub fn handle_string_entry(&mut self, table: &str, entry: &StringTableEntry) {
+        if table != "userinfo" {
+            return;
+        }
+        if let Some(user_info) = parse_user_info(entry) {
+            let user_id = UserId::from(user_info.player_info.user_id);
+            let player = self
+                .players
+                .entry(user_id)
+                .or_insert_with(|| TrackedPlayer {
+                    info: DemoPlayer {
+                        user_id,
+                        name: String::new(),
+                        steam_id: String::new(),
+                    },
+                    entity: user_info.entity_id,
+                    team: None,
+                    class: None,
+                });
+            // players can change their name during the game
+            player.info.name = user_info.player_info.name;
+            player.info.steam_id = user_info.player_info.steam_id;
+            player.entity = user_info.entity_id;
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::PlayerSpawn(spawn) => {
+                if let Some(player) = self.players.get_mut(&spawn.user_id.into()) {
+                    player.team = Team::from_number(spawn.team);
+                    player.class = Class::from_number(spawn.class);
+                }
+            }
+            GameEvent::PlayerTeam(change) => {
+                if let Some(player) = self.players.get_mut(&change.user_id.into()) {
+                    player.team = Team::from_number(change.team.into());
+                }
+            }
+            GameEvent::PlayerChangeClass(change) => {
+                if let Some(player) = self.players.get_mut(&change.user_id.into()) {
+                    player.class = Class::from_number(change.class);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Get the current state of a player, players that aren't in the userinfo table have an empty name and SteamID
+    pub fn state(&self, user_id: UserId) -> PlayerState {
+        match self.players.get(&user_id) {
+            Some(player) => PlayerState {
+                user_id,
+                name: player.info.name.clone(),
+                steam_id: player.info.steam_id.clone(),
+                team: player.team,
+                class: player.class,
+            },
+            None => PlayerState {
+                user_id,
+                name: String::new(),
+                steam_id: String::new(),
+                team: None,
+                class: None,
+            },
+        }
+    }
+
+    pub fn class(&self, user_id: UserId) -> Option<Class> {
+        self.players.get(&user_id).and_then(|player| player.class)
+    }
+
+    pub fn entity(&self, user_id: UserId) -> Option<EntityId> {
+        self.players.get(&user_id).map(|player| player.entity)
+    }
+
+    /// All players that are currently playing medic, with their team and entity
+    pub fn medics(&self) -> impl Iterator<Item = (UserId, Team, EntityId)> + '_ {
+        self.players
+            .iter()
+            .filter(|(_, player)| player.class == Some(Class::Medic))
+            .filter_map(|(user_id, player)| Some((*user_id, player.team?, player.entity)))
+    }
+
+    /// Find the player controlling a player entity
+    pub fn user_for_entity(&self, entity: EntityId) -> Option<UserId> {
+        self.players
+            .iter()
+            .find(|(_, player)| player.entity == entity)
+            .map(|(user_id, _)| *user_id)
+    }
+
+    pub fn team(&self, user_id: UserId) -> Option<Team> {
+        self.players.get(&user_id).and_then(|player| player.team)
+    }
+
+    pub fn players(&self) -> Vec<DemoPlayer> {
+        self.players
+            .values()
+            .map(|player| player.info.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,98 @@
+use crate::error::CutError;
+use crate::reader::PacketReader;
+use serde::Serialize;
+use std::ops::ControlFlow;
+
+/// How much of the source demo has been processed by a cut or highlight scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Progress {
+    /// Number of bytes of the source demo that have been processed
+    pub bytes: usize,
+    /// Size of the source demo in bytes
+    pub total_bytes: usize,
+    /// Tick of the last processed packet
+    pub tick: u32,
+    /// Number of ticks in the demo according to the header, 0 if the demo wasn't properly closed
+    pub total_ticks: u32,
+}
+
+impl Progress {
+    /// Fraction of the demo that has been processed, between 0 and 1
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes == 0 {
+            return 1.0;
+        }
+        self.bytes as f32 / self.total_bytes as f32
+    }
+}
+
+/// Number of bytes of the source demo to process between calls to the progress callback
+pub const PROGRESS_INTERVAL: usize = 1024 * 1024;
+
+/// Calls the progress callback every [`PROGRESS_INTERVAL`] bytes, turning a request to stop into an error
+pub(crate) struct ProgressTracker<'a> {
+    callback: Option<&'a mut dyn FnMut(Progress) -> ControlFlow<()>>,
+    total_bytes: usize,
+    total_ticks: u32,
+    next_report: usize,
+}
+
+impl<'a> ProgressTracker<'a> {
+    pub fn new(
+        callback: &'a mut dyn FnMut(Progress) -> ControlFlow<()>,
+        total_bytes: usize,
+        total_ticks: u32,
+    ) -> Self {
+        ProgressTracker {
+            callback: Some(callback),
+            total_bytes,
+            total_ticks,
+            next_report: 0,
+        }
+    }
+
+    /// A tracker that doesn't report anything
+    pub fn disabled() -> Self {
+        ProgressTracker {
+            callback: None,
+            total_bytes: 0,
+            total_ticks: 0,
+            next_report: usize::MAX,
+        }
+    }
+
+    /// Report the progress if enough of the demo has been processed since the last report
+    pub fn update(&mut self, packets: &PacketReader) -> Result<(), CutError> {
+        let bytes = packets.offset();
+        if bytes < self.next_report {
+            return Ok(());
+        }
+        self.next_report = bytes.saturating_add(PROGRESS_INTERVAL);
+        self.report(bytes, packets)
+    }
+
+    /// Report the final progress once processing is done
+    ///
+    /// A cut doesn't need to read the demo past the end of the last range, so this reports the full demo as processed
+    pub fn finish(&mut self, packets: &PacketReader) -> Result<(), CutError> {
+        self.report(self.total_bytes, packets)
+    }
+
+    fn report(&mut self, bytes: usize, packets: &PacketReader) -> Result<(), CutError> {
+        let position = packets.position();
+        let progress = Progress {
+            bytes: bytes.min(self.total_bytes),
+            total_bytes: self.total_bytes,
+            tick: position.tick,
+            total_ticks: self.total_ticks,
+        };
+        match self.callback.as_mut() {
+            Some(callback) => match callback(progress) {
+                ControlFlow::Continue(()) => Ok(()),
+                ControlFlow::Break(()) => Err(CutError::Cancelled { position }),
+            },
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::error::{CutError, PacketPosition};
+use tf_demo_parser::demo::packet::Packet;
+use tf_demo_parser::{Parse, ParserState, Stream};
+
+/// Reads the packets from a demo while keeping track of the position of the packet being read
+pub struct PacketReader<'a> {
+    stream: Stream<'a>,
+    position: PacketPosition,
+    read: usize,
+    ended: bool,
+}
+
+impl<'a> PacketReader<'a> {
+    pub fn new(stream: Stream<'a>) -> Self {
+        PacketReader {
+            stream,
+            position: PacketPosition::default(),
+            read: 0,
+            ended: false,
+        }
+    }
+
+    /// Continue reading from a packet boundary at `offset` bytes into the demo
+    ///
+    /// `position` is the position of the packet before the offset
+    pub fn resume(
+        mut stream: Stream<'a>,
+        offset: usize,
+        position: PacketPosition,
+    ) -> Result<Self, CutError> {
+        stream
+            .set_pos(offset * 8)
+            .map_err(CutError::parse(position))?;
+        Ok(PacketReader {
+            stream,
+            position,
+            read: position.index + 1,
+            ended: false,
+        })
+    }
+
+    pub fn next(&mut self, state: &ParserState) -> Result<Option<Packet<'a>>, CutError> {
+        // demos that weren't properly closed don't end with a stop packet
+        if self.ended || self.stream.bits_left() < 8 {
+            return Ok(None);
+        }
+
+        self.position.index = self.read;
+        let packet = Packet::parse(&mut self.stream, state).map_err(|e| {
+            self.ended = true;
+            CutError::parse(self.position)(e)
+        })?;
+        self.read += 1;
+        self.position.tick = packet.tick();
+        if let Packet::Stop(_) = packet {
+            self.ended = true;
+        }
+        Ok(Some(packet))
+    }
+
+    /// Position of the last packet read
+    pub fn position(&self) -> PacketPosition {
+        self.position
+    }
+
+    /// Byte offset of the next packet in the demo
+    pub fn offset(&self) -> usize {
+        self.stream.pos() / 8
+    }
+
+    pub fn is_ended(&self) -> bool {
+        self.ended || self.stream.bits_left() < 8
+    }
+}
@@ -3,12 +3,12 @@ use tf_demo_parser::demo::message::stringtable::{StringTableMeta, UpdateStringTa
 use tf_demo_parser::demo::message::Message;
 use tf_demo_parser::demo::packet::stringtable::StringTableEntry;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct StringTable {
     entries: BTreeMap<u16, StringTableEntry<'static>>,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct StringTablesUpdates {
     pub tables: BTreeMap<u8, StringTable>,
 }
@@ -26,6 +26,11 @@ impl StringTablesUpdates {
         }
     }
 
+    /// Total number of entries over all tables
+    pub fn entry_count(&self) -> usize {
+        self.tables.values().map(|table| table.entries.len()).sum()
+    }
+
     pub fn encode(self) -> impl IntoIterator<Item = UpdateStringTableMessage<'static>> {
         self.tables
             .into_iter()
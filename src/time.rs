@@ -0,0 +1,138 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+use tf_demo_parser::demo::header::Header;
+use thiserror::Error;
+
+/// Fallback tick interval for when the demo doesn't contain any timing information
+pub(crate) const DEFAULT_TICK_INTERVAL: f32 = 0.015;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("invalid time or tick '{0}', expected a tick number or a time like 'HH:MM:SS.fff', 'MM:SS' or '90s'")]
+pub struct TimeParseError(String);
+
+/// A point in the demo, either as tick or as time since the start of the demo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CutPoint {
+    Tick(u32),
+    Time(Duration),
+}
+
+impl CutPoint {
+    /// Convert the point to a tick, using the tick interval (in seconds) of the demo
+    pub fn to_tick(self, interval_per_tick: f32) -> u32 {
+        match self {
+            CutPoint::Tick(tick) => tick,
+            CutPoint::Time(time) => duration_to_ticks(time, interval_per_tick),
+        }
+    }
+}
+
+impl FromStr for CutPoint {
+    type Err = TimeParseError;
+
+    /// Parses plain numbers as ticks and anything with a `:` separator or `s` suffix as time
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(tick) = s.parse() {
+            Ok(CutPoint::Tick(tick))
+        } else {
+            parse_time(s).map(CutPoint::Time)
+        }
+    }
+}
+
+impl Display for CutPoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CutPoint::Tick(tick) => write!(f, "{}", tick),
+            CutPoint::Time(time) => write!(f, "{}", format_time(*time)),
+        }
+    }
+}
+
+/// Convert a time to a number of ticks, times beyond the last possible tick saturate at `u32::MAX`
+pub fn duration_to_ticks(time: Duration, interval_per_tick: f32) -> u32 {
+    let ticks = (time.as_secs_f64() / valid_interval(interval_per_tick) as f64).round();
+    if ticks >= u32::MAX as f64 {
+        u32::MAX
+    } else {
+        ticks as u32
+    }
+}
+
+/// Convert a number of ticks to a time, saturating at `Duration::MAX`
+pub fn ticks_to_duration(ticks: u32, interval_per_tick: f32) -> Duration {
+    Duration::try_from_secs_f64(ticks as f64 * valid_interval(interval_per_tick) as f64)
+        .unwrap_or(Duration::MAX)
+}
+
+/// Use the default interval for intervals that can't be used for conversions, like a 0 interval from a broken demo
+fn valid_interval(interval_per_tick: f32) -> f32 {
+    if interval_per_tick.is_finite() && interval_per_tick > 0.0 {
+        interval_per_tick
+    } else {
+        DEFAULT_TICK_INTERVAL
+    }
+}
+
+/// Parse a time in the form of `HH:MM:SS.fff`, `MM:SS.fff` or `SS.fffs`
+pub fn parse_time(input: &str) -> Result<Duration, TimeParseError> {
+    let err = || TimeParseError(input.to_string());
+    let input = input.trim();
+
+    let (parts, seconds) = match input.strip_suffix('s') {
+        Some(seconds) => (None, seconds),
+        None => match input.rsplit_once(':') {
+            Some((parts, seconds)) => (Some(parts), seconds),
+            None => return Err(err()),
+        },
+    };
+
+    let seconds: f64 = seconds.parse().map_err(|_| err())?;
+    if !seconds.is_finite() || seconds < 0.0 || (parts.is_some() && seconds >= 60.0) {
+        return Err(err());
+    }
+
+    let minutes = match parts {
+        None => 0,
+        Some(parts) => match parts.split_once(':') {
+            Some((hours, minutes)) => {
+                let hours: u64 = hours.parse().map_err(|_| err())?;
+                let minutes: u64 = minutes.parse().map_err(|_| err())?;
+                if minutes >= 60 {
+                    return Err(err());
+                }
+                hours
+                    .checked_mul(60)
+                    .and_then(|minutes_from_hours| minutes_from_hours.checked_add(minutes))
+                    .ok_or_else(err)?
+            }
+            None => parts.parse().map_err(|_| err())?,
+        },
+    };
+
+    // values too large for a duration are rejected instead of overflowing
+    let seconds = Duration::try_from_secs_f64(seconds).map_err(|_| err())?;
+    minutes
+        .checked_mul(60)
+        .and_then(|minutes| Duration::from_secs(minutes).checked_add(seconds))
+        .ok_or_else(err)
+}
+
+/// Format a time as `HH:MM:SS.fff`
+pub fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+pub(crate) fn header_tick_interval(header: &Header) -> Option<f32> {
+    let interval = header.duration / header.ticks as f32;
+    (interval.is_finite() && interval > 0.0).then(|| interval)
+}
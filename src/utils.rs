@@ -1,3 +1,5 @@
+use wasm_bindgen::JsValue;
+
 pub fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
     // `set_panic_hook` function at least once during initialization, and then
@@ -8,3 +10,7 @@ pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+pub fn js_error<E: ToString>(err: E) -> JsValue {
+    JsValue::from_str(&err.to_string())
+}
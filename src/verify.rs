@@ -0,0 +1,350 @@
+use crate::error::VerifyError;
+use crate::{clamp_range, TickRange};
+use std::fmt::{self, Display, Formatter};
+use tf_demo_parser::demo::header::Header;
+use tf_demo_parser::demo::message::packetentities::EntityId;
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::demo::packet::message::MessagePacketMeta;
+use tf_demo_parser::demo::packet::PacketType;
+use tf_demo_parser::demo::parser::gamestateanalyser::{GameState, GameStateAnalyser};
+use tf_demo_parser::demo::parser::handler::BorrowMessageHandler;
+use tf_demo_parser::demo::parser::{DemoHandler, MessageHandler, RawPacketStream};
+use tf_demo_parser::demo::sendprop::{SendProp, SendPropIdentifier};
+use tf_demo_parser::{Demo, DemoParser, MessageType, ParseError, ParserState};
+
+/// Number of ticks at the start of the cut that are skipped while the state is being rebuilt
+const SETTLE_TICKS: u32 = 10;
+
+/// Result of comparing a cut against the original demo
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyReport {
+    /// Number of ticks that were compared
+    pub ticks_compared: u32,
+    /// The first difference between the original and the cut, if any
+    pub divergence: Option<Divergence>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+impl Display for VerifyReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.divergence {
+            None => write!(
+                f,
+                "cut matches the original for {} ticks",
+                self.ticks_compared
+            ),
+            Some(divergence) => write!(
+                f,
+                "cut diverges after {} matching ticks: {}",
+                self.ticks_compared, divergence
+            ),
+        }
+    }
+}
+
+/// A difference between the original demo and the cut
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Tick in the original demo at which the difference was found
+    pub tick: u32,
+    pub kind: DivergenceKind,
+    /// The entity that differs, if the difference could be attributed to one
+    pub entity: Option<EntityId>,
+    /// The first prop of the entity that differs
+    pub prop: Option<SendPropIdentifier>,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at tick {}", self.kind, self.tick)?;
+        if let Some(entity) = self.entity {
+            write!(f, ", entity {}", u32::from(entity))?;
+        }
+        if let Some(prop) = &self.prop {
+            write!(f, ", prop {:?}", prop)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DivergenceKind {
+    /// The cut contains a different tick than the original
+    TickMismatch {
+        cut_tick: u32,
+    },
+    /// The cut ended before the end of the range
+    Truncated,
+    World,
+    Player,
+    Buildings,
+    /// The entity updates in the tick differ
+    Entity,
+}
+
+impl Display for DivergenceKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DivergenceKind::TickMismatch { cut_tick } => {
+                write!(f, "cut has tick {} instead", cut_tick)
+            }
+            DivergenceKind::Truncated => write!(f, "cut ends early"),
+            DivergenceKind::World => write!(f, "world state differs"),
+            DivergenceKind::Player => write!(f, "player state differs"),
+            DivergenceKind::Buildings => write!(f, "building state differs"),
+            DivergenceKind::Entity => write!(f, "entity update differs"),
+        }
+    }
+}
+
+/// Verify that `cut` contains the same game state as `original` for the ticks in `range`
+///
+/// Both demos are re-parsed, comparing the game state and the raw entity updates tick by tick.
+/// The first few ticks of the cut are skipped, since the state there is still being rebuilt.
+/// Cuts made with [`CutOptions::preserve_ticks`](crate::CutOptions::preserve_ticks) are detected from the tick
+/// of the first message packet in the cut, which is where the cut places the start of the range.
+pub fn verify_cut(
+    original: &[u8],
+    cut: &[u8],
+    range: TickRange,
+) -> Result<VerifyReport, VerifyError> {
+    let original_ticks = header_ticks(original).map_err(VerifyError::Original)?;
+    let range = clamp_range(range, original_ticks);
+    let cut_start = first_message_tick(cut).map_err(VerifyError::Cut)?;
+    let offset = range.start.saturating_sub(cut_start);
+
+    let state_report =
+        compare_ticks::<GameStateAnalyser, _>(original, cut, range, offset, compare_game_state)?;
+    let entity_report =
+        compare_ticks::<EntityDumper, _>(original, cut, range, offset, compare_entities)?;
+
+    // report whichever difference happens first, preferring the entity difference since it includes the prop
+    let state_first = match (&state_report.divergence, &entity_report.divergence) {
+        (Some(state), Some(entity)) => state.tick < entity.tick,
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+    Ok(if state_first {
+        state_report
+    } else {
+        entity_report
+    })
+}
+
+fn header_ticks(input: &[u8]) -> Result<u32, ParseError> {
+    let demo = Demo::new(&input);
+    Ok(Header::read(&mut demo.get_stream())?.ticks)
+}
+
+/// Tick of the first message packet in the demo
+///
+/// The packets before it are the signon packets which are copied from the original demo as is.
+fn first_message_tick(input: &[u8]) -> Result<u32, ParseError> {
+    let demo = Demo::new(&input);
+    let mut stream = demo.get_stream();
+    let header = Header::read(&mut stream)?;
+    let mut handler = DemoHandler::default();
+    handler.handle_header(&header);
+
+    let mut packets = RawPacketStream::new(stream);
+    while let Some(packet) = packets.next(&handler.state_handler)? {
+        if packet.packet_type() == PacketType::Message {
+            return Ok(packet.tick());
+        }
+        handler.handle_packet(packet)?;
+    }
+    Ok(0)
+}
+
+fn compare_ticks<A, F>(
+    original: &[u8],
+    cut: &[u8],
+    range: TickRange,
+    offset: u32,
+    compare: F,
+) -> Result<VerifyReport, VerifyError>
+where
+    A: BorrowMessageHandler + Default,
+    F: Fn(&A::Output, &A::Output) -> Option<Difference>,
+{
+    let original_demo = Demo::new(&original);
+    let cut_demo = Demo::new(&cut);
+
+    let original_parser = DemoParser::new_with_analyser(original_demo.get_stream(), A::default());
+    let cut_parser = DemoParser::new_with_analyser(cut_demo.get_stream(), A::default());
+
+    let mut original_ticks = original_parser.ticker().map_err(VerifyError::Original)?.1;
+    let mut cut_ticks = cut_parser.ticker().map_err(VerifyError::Cut)?.1;
+
+    let start = range.start + SETTLE_TICKS;
+    while let Some(tick) = original_ticks.next().map_err(VerifyError::Original)? {
+        if tick.tick > start {
+            break;
+        }
+    }
+    while let Some(tick) = cut_ticks.next().map_err(VerifyError::Cut)? {
+        if tick.tick + offset > start {
+            break;
+        }
+    }
+    original_ticks.next().map_err(VerifyError::Original)?;
+    cut_ticks.next().map_err(VerifyError::Cut)?;
+
+    let mut ticks_compared = 0;
+    let divergence = loop {
+        let original_tick = match original_ticks.next().map_err(VerifyError::Original)? {
+            Some(tick) if tick.tick < range.end => tick,
+            _ => break None,
+        };
+        let cut_tick = match cut_ticks.next().map_err(VerifyError::Cut)? {
+            Some(tick) => tick,
+            None => {
+                break Some(Divergence {
+                    tick: original_tick.tick,
+                    kind: DivergenceKind::Truncated,
+                    entity: None,
+                    prop: None,
+                })
+            }
+        };
+        if original_tick.tick != cut_tick.tick + offset {
+            break Some(Divergence {
+                tick: original_tick.tick,
+                kind: DivergenceKind::TickMismatch {
+                    cut_tick: cut_tick.tick,
+                },
+                entity: None,
+                prop: None,
+            });
+        }
+        if let Some((kind, entity, prop)) = compare(&original_tick.state, &cut_tick.state) {
+            break Some(Divergence {
+                tick: original_tick.tick,
+                kind,
+                entity,
+                prop,
+            });
+        }
+        ticks_compared += 1;
+    };
+
+    Ok(VerifyReport {
+        ticks_compared,
+        divergence,
+    })
+}
+
+/// The kind of difference in a tick, with the entity and prop that differ
+type Difference = (DivergenceKind, Option<EntityId>, Option<SendPropIdentifier>);
+
+fn compare_game_state(original: &GameState, cut: &GameState) -> Option<Difference> {
+    if original.world != cut.world {
+        return Some((DivergenceKind::World, None, None));
+    }
+    if original.players.len() != cut.players.len() {
+        return Some((DivergenceKind::Player, None, None));
+    }
+    if let Some(player) = original
+        .players
+        .iter()
+        .zip(&cut.players)
+        .find_map(|(original, cut)| (original != cut).then(|| original))
+    {
+        return Some((DivergenceKind::Player, Some(player.entity), None));
+    }
+    if original.buildings != cut.buildings {
+        return Some((DivergenceKind::Buildings, None, None));
+    }
+    None
+}
+
+fn compare_entities(original: &EntityDump, cut: &EntityDump) -> Option<Difference> {
+    for (original, cut) in original.iter().zip(cut) {
+        if original.0 != cut.0 {
+            return Some((DivergenceKind::Entity, Some(original.0), None));
+        }
+        let (original_baseline, original_props) = &original.1;
+        let (cut_baseline, cut_props) = &cut.1;
+        let prop = first_different_prop(original_baseline, cut_baseline)
+            .or_else(|| first_different_prop(original_props, cut_props));
+        if let Some(prop) = prop {
+            return Some((DivergenceKind::Entity, Some(original.0), prop));
+        }
+    }
+    match original.len().cmp(&cut.len()) {
+        std::cmp::Ordering::Greater => {
+            Some((DivergenceKind::Entity, Some(original[cut.len()].0), None))
+        }
+        std::cmp::Ordering::Less => {
+            Some((DivergenceKind::Entity, Some(cut[original.len()].0), None))
+        }
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+/// Find the first prop that differs between two prop lists
+///
+/// Returns `Some(None)` if the lists differ only in length and the extra prop can't be identified
+fn first_different_prop(
+    original: &[SendProp],
+    cut: &[SendProp],
+) -> Option<Option<SendPropIdentifier>> {
+    if let Some((original, _)) = original
+        .iter()
+        .zip(cut)
+        .find(|(original, cut)| original != cut)
+    {
+        return Some(Some(original.identifier));
+    }
+    if original.len() != cut.len() {
+        let extra = original.get(cut.len()).or_else(|| cut.get(original.len()));
+        return Some(extra.map(|prop| prop.identifier));
+    }
+    None
+}
+
+type EntityDump = Vec<(EntityId, (Vec<SendProp>, Vec<SendProp>))>;
+
+/// Collects the entity updates from every tick
+#[derive(Default)]
+struct EntityDumper {
+    entities: EntityDump,
+}
+
+impl MessageHandler for EntityDumper {
+    type Output = EntityDump;
+
+    fn does_handle(message_type: MessageType) -> bool {
+        matches!(message_type, MessageType::PacketEntities)
+    }
+
+    fn handle_message(&mut self, message: &Message, _tick: u32) {
+        if let Message::PacketEntities(entity_message) = message {
+            for entity in &entity_message.entities {
+                self.entities.push((
+                    entity.entity_index,
+                    (entity.baseline_props.clone(), entity.props.clone()),
+                ));
+            }
+        }
+    }
+
+    fn handle_packet_meta(&mut self, _tick: u32, _meta: &MessagePacketMeta) {
+        self.entities.clear();
+    }
+
+    fn into_output(self, _state: &ParserState) -> Self::Output {
+        self.entities
+    }
+}
+
+impl BorrowMessageHandler for EntityDumper {
+    fn borrow_output(&self, _state: &ParserState) -> &Self::Output {
+        &self.entities
+    }
+}
@@ -0,0 +1,163 @@
+use crate::time::{header_tick_interval, DEFAULT_TICK_INTERVAL};
+use bitbuffer::{BitWrite, BitWriteStream, LittleEndian};
+use std::cmp::max;
+use std::io::{self, Seek, SeekFrom, Write};
+use tf_demo_parser::demo::header::Header;
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::demo::packet::stop::StopPacket;
+use tf_demo_parser::demo::packet::{Packet, PacketType};
+use tf_demo_parser::demo::parser::Encode;
+use tf_demo_parser::{ParseError, ParserState};
+
+#[derive(Debug)]
+pub enum WriteError {
+    Encode(ParseError),
+    Io(io::Error),
+}
+
+impl From<ParseError> for WriteError {
+    fn from(e: ParseError) -> Self {
+        WriteError::Encode(e)
+    }
+}
+
+impl From<bitbuffer::BitError> for WriteError {
+    fn from(e: bitbuffer::BitError) -> Self {
+        WriteError::Encode(e.into())
+    }
+}
+
+impl From<io::Error> for WriteError {
+    fn from(e: io::Error) -> Self {
+        WriteError::Io(e)
+    }
+}
+
+/// Writes packets to a demo, keeping track of the information needed for the header
+///
+/// The header is written at the start as given, the actual tick count, duration, frame count and
+/// signon length are only known once the demo is finished and have to be written over it with
+/// [`rewrite_header`] if the sink can seek.
+/// Packets are encoded one at a time and written to the sink directly, so the output is never fully kept in memory.
+pub struct DemoWriter<W: Write> {
+    sink: W,
+    buffer: Vec<u8>,
+    header: Header,
+    interval_per_tick: Option<f32>,
+    ticks: u32,
+    frames: u32,
+    signon_length: u32,
+    length: u64,
+    packets: u32,
+}
+
+impl<W: Write> DemoWriter<W> {
+    /// Create a new writer, `header` is used for all fields that aren't calculated from the written packets
+    pub fn new(header: Header, mut sink: W) -> Result<Self, WriteError> {
+        let encoded_header = encode_header(&header)?;
+        sink.write_all(&encoded_header)?;
+        Ok(DemoWriter {
+            sink,
+            buffer: Vec::with_capacity(1024),
+            header,
+            interval_per_tick: None,
+            ticks: 0,
+            frames: 0,
+            signon_length: 0,
+            length: encoded_header.len() as u64,
+            packets: 0,
+        })
+    }
+
+    pub fn write_packet(&mut self, packet: &Packet, state: &ParserState) -> Result<(), WriteError> {
+        self.buffer.clear();
+        packet.encode(
+            &mut BitWriteStream::new(&mut self.buffer, LittleEndian),
+            state,
+        )?;
+        self.sink.write_all(&self.buffer)?;
+        let length = self.buffer.len() as u32;
+        self.length += length as u64;
+        self.packets += 1;
+
+        match packet {
+            Packet::Signon(message_packet) => {
+                self.signon_length += length;
+                for message in &message_packet.messages {
+                    if let Message::ServerInfo(info) = message {
+                        self.interval_per_tick = Some(info.interval_per_tick);
+                    }
+                }
+            }
+            Packet::Message(_) => {
+                self.frames += 1;
+            }
+            _ => {}
+        }
+        self.ticks = max(self.ticks, packet.tick());
+
+        Ok(())
+    }
+
+    /// Number of packets written so far, not counting the stop packet
+    pub fn packets_written(&self) -> u32 {
+        self.packets
+    }
+
+    /// Write the stop packet and calculate the final header
+    ///
+    /// Returns the sink, the total number of bytes written and the header for the written packets
+    pub fn finish(
+        mut self,
+        tick: u32,
+        state: &ParserState,
+    ) -> Result<(W, u64, Header), WriteError> {
+        self.buffer.clear();
+        {
+            let mut stream = BitWriteStream::new(&mut self.buffer, LittleEndian);
+            PacketType::Stop.write(&mut stream)?;
+            StopPacket { tick }.encode(&mut stream, state)?;
+        }
+        self.sink.write_all(&self.buffer)?;
+        self.length += self.buffer.len() as u64;
+        self.ticks = max(self.ticks, tick);
+
+        let mut header = self.header.clone();
+        // keep the interval of the source demo if the output contains no server info
+        let interval_per_tick = self
+            .interval_per_tick
+            .or_else(|| header_tick_interval(&header))
+            .unwrap_or(DEFAULT_TICK_INTERVAL);
+        header.ticks = self.ticks;
+        header.frames = self.frames;
+        header.signon = self.signon_length;
+        header.duration = self.ticks as f32 * interval_per_tick;
+
+        self.sink.flush()?;
+
+        Ok((self.sink, self.length, header))
+    }
+}
+
+fn encode_header(header: &Header) -> Result<Vec<u8>, WriteError> {
+    let mut data = Vec::with_capacity(1072);
+    header.write(&mut BitWriteStream::new(&mut data, LittleEndian))?;
+    Ok(data)
+}
+
+/// Write the header of a finished demo over the header written at `start`
+///
+/// The sink is left at the position it was at before writing the header.
+pub fn rewrite_header<W: Write + Seek>(
+    sink: &mut W,
+    start: u64,
+    header: &Header,
+) -> Result<(), WriteError> {
+    let header = encode_header(header)?;
+    let end = sink.stream_position()?;
+    sink.seek(SeekFrom::Start(start))?;
+    sink.write_all(&header)?;
+    sink.seek(SeekFrom::Start(end))?;
+    sink.flush()?;
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use democutter::{cut, try_cut_batch, TickRange};
+use std::fs;
+
+#[test]
+fn test_batch_matches_single_cuts() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let ranges = [
+        TickRange::new(30000, 50000),
+        TickRange::new(12000, 13500),
+        TickRange::new(31000, 32000),
+    ];
+    let outputs = try_cut_batch(&file, &ranges).unwrap();
+
+    assert_eq!(ranges.len(), outputs.len());
+    for (range, output) in ranges.iter().zip(outputs) {
+        assert_eq!(cut(&file, range.start, range.end), output);
+    }
+}
@@ -0,0 +1,195 @@
+use democutter::{bookmarks, try_bookmarks_with_options, Class, HighlightOptions, HighlightSource};
+use std::fs;
+
+#[test]
+fn test_bookmarks_players() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let bookmarks = bookmarks(&file);
+
+    assert!(!bookmarks.players.is_empty());
+    for highlight in &bookmarks.highlights {
+        let state = match &highlight.player {
+            Some(state) => state,
+            None => continue,
+        };
+        let player = bookmarks
+            .players
+            .iter()
+            .find(|player| player.user_id == state.user_id);
+        if let Some(player) = player {
+            assert_eq!(player.steam_id, state.steam_id);
+        }
+    }
+
+    let json = serde_json::to_value(&bookmarks).unwrap();
+    assert!(json["highlights"].is_array());
+    assert!(json["players"][0]["steamId"].is_string());
+}
+
+#[test]
+fn test_suggested_range() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let bookmarks = bookmarks(&file);
+    let interval = 1.0 / 66.0;
+
+    for highlight in &bookmarks.highlights {
+        let range = highlight.suggested_range(interval);
+        assert!(range.start <= highlight.tick);
+        assert!(range.end >= highlight.tick);
+    }
+}
+
+#[test]
+fn test_kill_highlights() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let options = HighlightOptions {
+        multi_kill_window: 5.0,
+        killstreak_interval: 3,
+        ..HighlightOptions::default()
+    };
+    let bookmarks = try_bookmarks_with_options(&file, &options).unwrap();
+
+    assert!(bookmarks
+        .highlights
+        .windows(2)
+        .all(|pair| pair[0].tick <= pair[1].tick));
+    for highlight in &bookmarks.highlights {
+        match &highlight.source {
+            HighlightSource::MultiKill { ticks, .. } => {
+                assert_eq!(highlight.tick, ticks.start);
+                assert!(ticks.start <= ticks.end);
+            }
+            HighlightSource::Killstreak { kills, ticks } => {
+                assert_eq!(0, kills % 3);
+                assert_eq!(highlight.tick, ticks.end);
+            }
+            _ => {}
+        }
+    }
+
+    let disabled = HighlightOptions {
+        killstreak_interval: 0,
+        ..options
+    };
+    let bookmarks = try_bookmarks_with_options(&file, &disabled).unwrap();
+    assert!(!bookmarks
+        .highlights
+        .iter()
+        .any(|highlight| matches!(highlight.source, HighlightSource::Killstreak { .. })));
+}
+
+#[test]
+fn test_special_kills() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let bookmarks = bookmarks(&file);
+
+    for highlight in &bookmarks.highlights {
+        let kill = match &highlight.source {
+            HighlightSource::Headshot { kill, scope } => {
+                if scope.is_some() {
+                    assert_eq!(Some(Class::Sniper), kill.attacker.class);
+                }
+                kill
+            }
+            HighlightSource::Backstab(kill) => {
+                assert_eq!(Some(Class::Spy), kill.attacker.class);
+                kill
+            }
+            HighlightSource::Reflect(kill) => {
+                assert!(kill.weapon.starts_with("deflect_"));
+                kill
+            }
+            HighlightSource::MarketGarden(kill)
+            | HighlightSource::TauntKill(kill)
+            | HighlightSource::Telefrag(kill) => kill,
+            _ => continue,
+        };
+        assert_eq!(
+            highlight.player.as_ref().unwrap().user_id,
+            kill.attacker.user_id
+        );
+        assert_ne!(kill.attacker.user_id, kill.victim.user_id);
+    }
+}
+
+#[test]
+fn test_airshots() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let options = HighlightOptions::default();
+    let bookmarks = try_bookmarks_with_options(&file, &options).unwrap();
+
+    for highlight in &bookmarks.highlights {
+        if let HighlightSource::AirShot {
+            attacker,
+            victim,
+            height,
+        } = &highlight.source
+        {
+            assert_eq!(highlight.player.as_ref().unwrap().user_id, attacker.user_id);
+            assert_ne!(attacker.user_id, victim.user_id);
+            assert!(*height >= options.airshot_min_height);
+        }
+    }
+}
+
+#[test]
+fn test_uber_highlights() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let bookmarks = bookmarks(&file);
+
+    for highlight in &bookmarks.highlights {
+        let uber = match &highlight.source {
+            HighlightSource::UberDropped(uber) => {
+                assert!(uber.charge >= 1.0);
+                uber
+            }
+            HighlightSource::UberUsed(uber)
+            | HighlightSource::ForcedUber(uber)
+            | HighlightSource::UberAdvantage(uber) => uber,
+            _ => continue,
+        };
+        assert_eq!(
+            highlight.player.as_ref().unwrap().user_id,
+            uber.medic.user_id
+        );
+        assert_eq!(uber.team, uber.medic.team);
+        assert!((0.0..=1.0).contains(&uber.charge));
+    }
+}
+
+#[test]
+fn test_round_highlights() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let bookmarks = bookmarks(&file);
+
+    let mut last_round = 0;
+    for highlight in &bookmarks.highlights {
+        match &highlight.source {
+            HighlightSource::RoundStart { round }
+            | HighlightSource::RoundWin { round, .. }
+            | HighlightSource::RoundStalemate { round } => {
+                assert!(highlight.player.is_none());
+                assert!(*round >= last_round);
+                last_round = *round;
+            }
+            HighlightSource::TeamWipe { ticks, .. } => {
+                assert!(highlight.player.is_none());
+                assert_eq!(highlight.tick, ticks.start);
+            }
+            HighlightSource::PointCaptured { team, cappers, .. } => {
+                assert!(!cappers.is_empty());
+                assert!(cappers.iter().all(|capper| capper.team == Some(*team)));
+                assert_eq!(
+                    highlight.player.as_ref().unwrap().user_id,
+                    cappers[0].user_id
+                );
+            }
+            HighlightSource::PointBlocked { team, .. }
+            | HighlightSource::IntelCaptured { team } => {
+                assert_eq!(Some(*team), highlight.player.as_ref().unwrap().team);
+            }
+            _ => {}
+        }
+    }
+    assert!(last_round > 0);
+}
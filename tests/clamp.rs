@@ -0,0 +1,83 @@
+use democutter::{try_cut, try_cut_with_report, CutOptions, TickRange};
+use std::fs;
+use tf_demo_parser::demo::header::Header;
+use tf_demo_parser::Demo;
+
+fn header_ticks(input: &[u8]) -> u32 {
+    let demo = Demo::new(&input);
+    Header::read(&mut demo.get_stream()).unwrap().ticks
+}
+
+#[test]
+fn test_cut_from_start() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let output = try_cut(&file, 0, 2000).unwrap();
+    assert_eq!(2000, header_ticks(&output));
+}
+
+#[test]
+fn test_cut_whole_demo() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let demo_ticks = header_ticks(&file);
+
+    let (_, report) = try_cut_with_report(
+        &file,
+        &[TickRange::new(0, u32::MAX)],
+        &CutOptions::default(),
+    )
+    .unwrap();
+    assert!(report.clamped);
+    assert_eq!(1, report.ranges.len());
+    assert_eq!(0, report.ranges[0].start);
+    // the last packet can be a few ticks before the end of the demo
+    assert!(report.ranges[0].end <= demo_ticks);
+    assert!(report.ranges[0].end + 10 > demo_ticks);
+}
+
+#[test]
+fn test_unclamped_report() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+
+    let (_, report) = try_cut_with_report(
+        &file,
+        &[TickRange::new(30000, 50000)],
+        &CutOptions::default(),
+    )
+    .unwrap();
+    assert!(!report.clamped);
+    assert_eq!(vec![TickRange::new(30000, 50000)], report.ranges);
+}
+
+#[test]
+fn test_start_past_end() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let demo_ticks = header_ticks(&file);
+
+    let (output, report) = try_cut_with_report(
+        &file,
+        &[TickRange::new(demo_ticks + 100, demo_ticks + 1000)],
+        &CutOptions::default(),
+    )
+    .unwrap();
+    assert!(report.clamped);
+    assert!(report.ranges[0].start < demo_ticks);
+    assert!(header_ticks(&output) > 0);
+}
+
+#[test]
+fn test_report_counts() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+
+    let (output, report) = try_cut_with_report(
+        &file,
+        &[TickRange::new(30000, 50000)],
+        &CutOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(output.len() as u64, report.output_size);
+    assert!(report.packets_written > report.synthesized_packets);
+    assert!(report.synthesized_packets > 0);
+    assert_eq!(3, report.synthesized_ticks);
+    assert!(report.entities > 0);
+    assert!(report.string_table_entries > 0);
+}
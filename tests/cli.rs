@@ -0,0 +1,107 @@
+use democutter::{cut, DemoIndex};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn democutter() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_democutter"))
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("democutter-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn test_cut_to_file() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let output = temp_path("cut.dem");
+
+    let status = democutter()
+        .args(["cut", "test_data/gully.dem", "30000", "50000", "-o"])
+        .arg(&output)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(cut(&file, 30000, 50000), fs::read(&output).unwrap());
+    fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn test_cut_to_stdout() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+
+    let output = democutter()
+        .args([
+            "cut",
+            "test_data/gully.dem",
+            "--range",
+            "30000-50000",
+            "-o",
+            "-",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(cut(&file, 30000, 50000), output.stdout);
+}
+
+#[test]
+fn test_cut_ignores_stale_index() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let demo = temp_path("stale.dem");
+    let output = temp_path("stale_cut.dem");
+    fs::write(&demo, &file).unwrap();
+    DemoIndex::new(&file, 4000)
+        .unwrap()
+        .save_sidecar(&demo)
+        .unwrap();
+
+    // bytes after the stop packet change the size and hash without changing the demo
+    let mut changed = file.clone();
+    changed.extend_from_slice(&[0; 16]);
+    fs::write(&demo, &changed).unwrap();
+
+    let status = democutter()
+        .arg("cut")
+        .arg(&demo)
+        .args(["30000", "50000", "-o"])
+        .arg(&output)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(cut(&changed, 30000, 50000), fs::read(&output).unwrap());
+
+    fs::remove_file(output).unwrap();
+    fs::remove_file(DemoIndex::sidecar_path(&demo)).unwrap();
+    fs::remove_file(demo).unwrap();
+}
+
+#[test]
+fn test_exit_codes() {
+    let missing = democutter()
+        .args(["info", "test_data/missing.dem"])
+        .status()
+        .unwrap();
+    assert_eq!(Some(3), missing.code());
+
+    let no_range = democutter()
+        .args(["cut", "test_data/gully.dem"])
+        .status()
+        .unwrap();
+    assert_eq!(Some(2), no_range.code());
+
+    let output = temp_path("verify.dem");
+    fs::write(
+        &output,
+        cut(&fs::read("test_data/gully.dem").unwrap(), 30000, 50000),
+    )
+    .unwrap();
+    let wrong_range = democutter()
+        .args(["verify", "test_data/gully.dem"])
+        .arg(&output)
+        .args(["31000", "51000"])
+        .status()
+        .unwrap();
+    assert_eq!(Some(4), wrong_range.code());
+    fs::remove_file(output).unwrap();
+}
@@ -0,0 +1,58 @@
+use democutter::cut;
+use std::collections::BTreeSet;
+use std::fs;
+use tf_demo_parser::demo::header::Header;
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::demo::packet::Packet;
+use tf_demo_parser::demo::parser::DemoHandler;
+use tf_demo_parser::{Demo, Parse};
+
+/// Every entity delta in the output should reference a tick the output contains entities for
+#[test]
+fn test_deltas_reference_written_ticks() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let output = cut(&file, 30000, 50000);
+
+    let demo = Demo::new(&output);
+    let mut stream = demo.get_stream();
+    let header = Header::read(&mut stream).unwrap();
+
+    let mut handler = DemoHandler::default();
+    handler.handle_header(&header);
+
+    let mut entity_ticks = BTreeSet::new();
+    let mut server_tick = 0;
+    let mut start_packets = 0;
+    loop {
+        let packet = Packet::parse(&mut stream, &handler.state_handler).unwrap();
+        match &packet {
+            Packet::Stop(_) => break,
+            Packet::Message(message_packet) => {
+                if message_packet.tick == 0 {
+                    start_packets += 1;
+                }
+                for message in &message_packet.messages {
+                    match message {
+                        Message::NetTick(net_tick) => server_tick = net_tick.tick,
+                        Message::PacketEntities(entities) => {
+                            if let Some(delta) = entities.delta {
+                                assert!(
+                                    entity_ticks.contains(&delta.get()),
+                                    "delta {} at server tick {} references a missing tick",
+                                    delta,
+                                    server_tick
+                                );
+                            }
+                            entity_ticks.insert(server_tick);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        handler.handle_packet(packet).unwrap();
+    }
+    // string tables, baselines and entities, no packets for every tick up to the server tick
+    assert!(start_packets < 50, "{} packets at the start", start_packets);
+}
@@ -0,0 +1,30 @@
+use democutter::{try_bookmarks, try_cut, CutError};
+use std::fs;
+
+#[test]
+fn test_invalid_header() {
+    let result = try_cut(&[0; 16], 0, 100);
+    assert!(matches!(result, Err(CutError::Header(_))));
+}
+
+#[test]
+fn test_truncated_demo() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let truncated = &file[0..file.len() / 2];
+
+    match try_cut(truncated, 1000, u32::MAX) {
+        Err(CutError::Parse { position, .. }) => {
+            assert!(position.index > 0);
+            assert!(position.tick > 1000);
+        }
+        result => panic!(
+            "expected parse error, got {:?}",
+            result.map(|out| out.len())
+        ),
+    }
+
+    assert!(matches!(
+        try_bookmarks(truncated),
+        Err(CutError::Parse { .. })
+    ));
+}
@@ -0,0 +1,50 @@
+use democutter::cut;
+use std::fs;
+use tf_demo_parser::demo::header::Header;
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::demo::packet::Packet;
+use tf_demo_parser::demo::parser::DemoHandler;
+use tf_demo_parser::{Demo, Parse};
+
+#[test]
+fn test_header_matches_output() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let output = cut(&file, 30000, 50000);
+
+    let demo = Demo::new(&output);
+    let mut stream = demo.get_stream();
+    let header = Header::read(&mut stream).unwrap();
+
+    let mut handler = DemoHandler::default();
+    handler.handle_header(&header);
+
+    let mut frames = 0;
+    let mut signon_length = 0;
+    let mut interval_per_tick = None;
+    let last_tick = loop {
+        let start = stream.pos();
+        let packet = Packet::parse(&mut stream, &handler.state_handler).unwrap();
+        let length = (stream.pos() - start) / 8;
+        match &packet {
+            Packet::Signon(message_packet) => {
+                signon_length += length;
+                for message in &message_packet.messages {
+                    if let Message::ServerInfo(info) = message {
+                        interval_per_tick = Some(info.interval_per_tick);
+                    }
+                }
+            }
+            Packet::Message(_) => frames += 1,
+            Packet::Stop(stop) => break stop.tick,
+            _ => {}
+        }
+        handler.handle_packet(packet).unwrap();
+    };
+
+    assert_eq!(20000, header.ticks);
+    assert_eq!(last_tick, header.ticks);
+    assert_eq!(frames, header.frames);
+    assert_eq!(signon_length as u32, header.signon);
+    let expected_duration = header.ticks as f32 * interval_per_tick.unwrap();
+    assert!((expected_duration - header.duration).abs() < 0.01);
+}
@@ -0,0 +1,970 @@
+use bitbuffer::{BitReadBuffer, BitReadStream, BitWriteStream, LittleEndian};
+use democutter::{
+    Bookmarks, Class, Highlight, HighlightAnalyser, HighlightOptions, HighlightSource, MultiKill,
+    Team, TickRange,
+};
+use pretty_assertions::assert_eq;
+use std::borrow::Cow;
+use tf_demo_parser::demo::data::userinfo::PlayerInfo;
+use tf_demo_parser::demo::gameevent_gen::{
+    GameEvent, MedicDeathEvent, PlayerChangeClassEvent, PlayerChargeDeployedEvent,
+    PlayerDeathEvent, PlayerHurtEvent, PlayerSpawnEvent, PlayerTeamEvent, RocketJumpEvent,
+    RocketJumpLandedEvent, TeamPlayCaptureBlockedEvent, TeamPlayFlagEventEvent,
+    TeamPlayPointCapturedEvent, TeamPlayRoundStalemateEvent, TeamPlayRoundStartEvent,
+    TeamPlayRoundWinEvent,
+};
+use tf_demo_parser::demo::message::gameevent::GameEventMessage;
+use tf_demo_parser::demo::message::packetentities::{
+    EntityId, PacketEntitiesMessage, PacketEntity, UpdateType,
+};
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::demo::packet::datatable::ClassId;
+use tf_demo_parser::demo::packet::stringtable::{ExtraData, StringTableEntry};
+use tf_demo_parser::demo::parser::analyser::UserId;
+use tf_demo_parser::demo::parser::MessageHandler;
+use tf_demo_parser::demo::sendprop::{SendProp, SendPropIdentifier, SendPropValue};
+use tf_demo_parser::ParserState;
+
+const RED: u16 = 2;
+const BLUE: u16 = 3;
+const SCOUT: u16 = 1;
+const SOLDIER: u16 = 3;
+const MEDIC: u16 = 5;
+const SPY: u16 = 8;
+
+const FLAGS: SendPropIdentifier = SendPropIdentifier::new("DT_BasePlayer", "m_fFlags");
+const ORIGIN_Z: SendPropIdentifier =
+    SendPropIdentifier::new("DT_TFNonLocalPlayerExclusive", "m_vecOrigin[2]");
+const FL_ONGROUND: i64 = 1;
+const CHARGE_LEVEL: SendPropIdentifier =
+    SendPropIdentifier::new("DT_TFWeaponMedigunDataNonLocal", "m_flChargeLevel");
+const OWNER_ENTITY: SendPropIdentifier = SendPropIdentifier::new("DT_BaseEntity", "m_hOwnerEntity");
+/// `weapon_id` of the direct hit in the hurt event
+const WEAPON_DIRECTHIT: u16 = 65;
+const WEAPON_ROCKETLAUNCHER: u16 = 22;
+const WEAPON_SNIPERRIFLE: u16 = 17;
+
+/// Feeds synthetic messages to a highlight analyser
+struct Events {
+    analyser: HighlightAnalyser,
+}
+
+impl Events {
+    fn new(options: HighlightOptions) -> Self {
+        Events {
+            analyser: HighlightAnalyser::new(options),
+        }
+    }
+
+    /// Add a player to the userinfo table and spawn them, the entity of the player has the same index as the user id
+    fn player(&mut self, tick: u32, user_id: u16, team: u16, class: u16) {
+        let player_info = PlayerInfo {
+            name: format!("player {}", user_id),
+            user_id: user_id as u32,
+            steam_id: format!("[U:1:{}]", user_id),
+            extra: 0,
+            friends_id: 0,
+            friends_name_bytes: [0; 32],
+            is_fake_player: 0,
+            is_hl_tv: 0,
+            is_replay: 0,
+            custom_file: [0; 4],
+            files_downloaded: 0,
+            more_extra: 0,
+        };
+        let mut data = Vec::new();
+        {
+            let mut stream = BitWriteStream::new(&mut data, LittleEndian);
+            stream.write(&player_info).unwrap();
+        }
+        // the text of a userinfo entry is the index of the client, one less than the entity index
+        let entry = StringTableEntry {
+            text: Some(Cow::Owned((user_id - 1).to_string())),
+            extra_data: Some(ExtraData::new(BitReadStream::new(
+                BitReadBuffer::new_owned(data, LittleEndian),
+            ))),
+        };
+        self.analyser
+            .handle_string_entry("userinfo", user_id as usize - 1, &entry);
+        self.event(
+            tick,
+            GameEvent::PlayerSpawn(PlayerSpawnEvent {
+                user_id,
+                team,
+                class,
+            }),
+        );
+    }
+
+    fn event(&mut self, tick: u32, event: GameEvent) {
+        let event_type_id = BitReadStream::new(BitReadBuffer::new(&[0, 0], LittleEndian))
+            .read()
+            .unwrap();
+        self.analyser.handle_message(
+            &Message::GameEvent(GameEventMessage {
+                event_type_id,
+                event,
+            }),
+            tick,
+        );
+    }
+
+    fn kill(&mut self, tick: u32, attacker: u16, victim: u16) {
+        self.kill_with(tick, attacker, victim, "tf_projectile_rocket", 0);
+    }
+
+    fn kill_with(&mut self, tick: u32, attacker: u16, victim: u16, weapon: &str, custom_kill: u16) {
+        self.event(tick, death(attacker, victim, weapon, custom_kill));
+    }
+
+    /// Update the flags and height of a player entity
+    fn entity(&mut self, tick: u32, entity: u16, update_type: UpdateType, on_ground: bool, z: f32) {
+        let flags = if on_ground { FL_ONGROUND } else { 0 };
+        self.entities(
+            tick,
+            vec![(
+                entity,
+                update_type,
+                vec![
+                    (FLAGS, SendPropValue::Integer(flags)),
+                    (ORIGIN_Z, SendPropValue::Float(z)),
+                ],
+            )],
+        );
+    }
+
+    /// Send the props for a number of entities, the props of entering entities are sent as baseline
+    fn entities(
+        &mut self,
+        tick: u32,
+        entities: Vec<(u16, UpdateType, Vec<(SendPropIdentifier, SendPropValue)>)>,
+    ) {
+        let entities = entities
+            .into_iter()
+            .map(|(entity, update_type, props)| {
+                let props = props
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, (identifier, value))| SendProp {
+                        index: index as u32,
+                        identifier,
+                        value,
+                    })
+                    .collect::<Vec<_>>();
+                let (baseline_props, props) = match update_type {
+                    UpdateType::Enter => (props, vec![]),
+                    UpdateType::Leave | UpdateType::Delete => (vec![], vec![]),
+                    _ => (vec![], props),
+                };
+                PacketEntity {
+                    server_class: ClassId::from(0),
+                    entity_index: EntityId::from(entity as u32),
+                    baseline_props,
+                    props,
+                    in_pvs: !matches!(update_type, UpdateType::Leave | UpdateType::Delete),
+                    update_type,
+                    serial_number: 0,
+                    delay: None,
+                }
+            })
+            .collect();
+        let message = PacketEntitiesMessage {
+            entities,
+            removed_entities: vec![],
+            max_entries: 32,
+            delta: None,
+            base_line: 0,
+            updated_base_line: false,
+        };
+        self.analyser
+            .handle_message(&Message::PacketEntities(message), tick);
+    }
+
+    fn hurt(&mut self, tick: u32, attacker: u16, victim: u16, weapon_id: u16) {
+        self.event(
+            tick,
+            GameEvent::PlayerHurt(PlayerHurtEvent {
+                user_id: victim,
+                health: 50,
+                attacker,
+                damage_amount: 100,
+                custom: 0,
+                show_disguised_crit: false,
+                crit: false,
+                mini_crit: false,
+                all_see_crit: false,
+                weapon_id,
+                bonus_effect: 0,
+            }),
+        );
+    }
+
+    fn finish(self) -> Bookmarks {
+        self.analyser
+            .into_output(&ParserState::new(24, HighlightAnalyser::does_handle, false))
+    }
+}
+
+fn death(attacker: u16, victim: u16, weapon: &str, custom_kill: u16) -> GameEvent {
+    GameEvent::PlayerDeath(Box::new(PlayerDeathEvent {
+        user_id: victim,
+        victim_ent_index: victim as u32,
+        inflictor_ent_index: attacker as u32,
+        attacker,
+        weapon: weapon.to_string(),
+        weapon_id: 0,
+        damage_bits: 0,
+        custom_kill,
+        assister: 0,
+        weapon_log_class_name: weapon.to_string(),
+        stun_flags: 0,
+        death_flags: 0,
+        silent_kill: false,
+        player_penetrate_count: 0,
+        assister_fallback: String::new(),
+        kill_streak_total: 0,
+        kill_streak_wep: 0,
+        kill_streak_assist: 0,
+        kill_streak_victim: 0,
+        ducks_streaked: 0,
+        duck_streak_total: 0,
+        duck_streak_assist: 0,
+        duck_streak_victim: 0,
+        rocket_jump: false,
+        weapon_def_index: 0,
+        crit_type: 0,
+    }))
+}
+
+/// The user id of the player that made the highlight
+fn user(highlight: &Highlight) -> Option<u8> {
+    highlight.player.as_ref().map(|player| player.user_id.0)
+}
+
+fn multi_kills(bookmarks: &Bookmarks) -> Vec<(u32, Option<u8>, MultiKill, TickRange)> {
+    bookmarks
+        .highlights
+        .iter()
+        .filter_map(|highlight| match &highlight.source {
+            HighlightSource::MultiKill { kind, ticks } => {
+                Some((highlight.tick, user(highlight), *kind, *ticks))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn killstreaks(bookmarks: &Bookmarks) -> Vec<(u32, Option<u8>, u32, TickRange)> {
+    bookmarks
+        .highlights
+        .iter()
+        .filter_map(|highlight| match &highlight.source {
+            HighlightSource::Killstreak { kills, ticks } => {
+                Some((highlight.tick, user(highlight), *kills, *ticks))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// A soldier on red and four scouts on blue
+fn teams() -> Events {
+    let mut events = Events::new(HighlightOptions::default());
+    events.player(1, 2, RED, SOLDIER);
+    for victim in 3..7 {
+        events.player(1, victim, BLUE, SCOUT);
+    }
+    events
+}
+
+#[test]
+fn test_multi_kill_kinds() {
+    let mut events = teams();
+    // the default window is 5 seconds, 333 ticks at the default tick interval
+    events.kill(100, 2, 3);
+    events.kill(200, 2, 4);
+    events.kill(1000, 2, 5);
+    events.kill(1300, 2, 6);
+    events.kill(1629, 2, 3);
+    events.kill(3000, 2, 4);
+
+    assert_eq!(
+        vec![
+            (100, Some(2), MultiKill::Double, TickRange::new(100, 200)),
+            (1000, Some(2), MultiKill::Triple, TickRange::new(1000, 1629)),
+        ],
+        multi_kills(&events.finish())
+    );
+}
+
+#[test]
+fn test_multi_kill_quad() {
+    let mut events = teams();
+    for (tick, victim) in [(100, 3), (150, 4), (200, 5), (250, 6), (300, 3)] {
+        events.kill(tick, 2, victim);
+    }
+
+    assert_eq!(
+        vec![(100, Some(2), MultiKill::Quad, TickRange::new(100, 300))],
+        multi_kills(&events.finish())
+    );
+}
+
+#[test]
+fn test_multi_kill_ends_on_death() {
+    let mut events = teams();
+    events.kill(100, 2, 3);
+    events.kill(150, 2, 4);
+    events.kill(200, 5, 2);
+    // a new life starts a new chain
+    events.kill(250, 2, 6);
+
+    assert_eq!(
+        vec![(100, Some(2), MultiKill::Double, TickRange::new(100, 150))],
+        multi_kills(&events.finish())
+    );
+}
+
+#[test]
+fn test_multi_kill_ignores_suicides_and_world() {
+    let mut events = teams();
+    events.kill(100, 0, 3);
+    events.kill(150, 4, 4);
+    events.kill(200, 2, 5);
+
+    assert!(multi_kills(&events.finish()).is_empty());
+}
+
+#[test]
+fn test_multi_kill_player_at_last_kill() {
+    let mut events = teams();
+    events.kill(100, 2, 3);
+    events.kill(150, 2, 4);
+    // the chain expires after the class change, but belongs to the soldier
+    events.event(
+        200,
+        GameEvent::PlayerChangeClass(PlayerChangeClassEvent {
+            user_id: 2,
+            class: SCOUT,
+        }),
+    );
+    events.kill(2000, 3, 4);
+
+    let bookmarks = events.finish();
+    let multi_kill = bookmarks
+        .highlights
+        .iter()
+        .find(|highlight| matches!(highlight.source, HighlightSource::MultiKill { .. }))
+        .unwrap();
+    let player = multi_kill.player.as_ref().unwrap();
+    assert_eq!(UserId(2), player.user_id);
+    assert_eq!("player 2", player.name);
+    assert_eq!(Some(Team::Red), player.team);
+    assert_eq!(Some(Class::Soldier), player.class);
+}
+
+#[test]
+fn test_multi_kill_order() {
+    let mut events = Events::new(HighlightOptions::default());
+    for user in [2, 3, 4, 5] {
+        events.player(1, user, RED, SOLDIER);
+    }
+    for user in [6, 7, 8, 9] {
+        events.player(1, user, BLUE, SCOUT);
+    }
+    // both chains expire at the same time
+    events.kill(100, 7, 4);
+    events.kill(100, 2, 8);
+    events.kill(110, 7, 5);
+    events.kill(110, 2, 9);
+
+    assert_eq!(
+        vec![
+            (100, Some(2), MultiKill::Double, TickRange::new(100, 110)),
+            (100, Some(7), MultiKill::Double, TickRange::new(100, 110)),
+        ],
+        multi_kills(&events.finish())
+    );
+}
+
+#[test]
+fn test_killstreak() {
+    let mut events = Events::new(HighlightOptions {
+        killstreak_interval: 2,
+        ..HighlightOptions::default()
+    });
+    events.player(1, 2, RED, SOLDIER);
+    events.player(1, 3, BLUE, SCOUT);
+    for tick in [1000, 2000, 3000, 4000, 5000] {
+        events.kill(tick, 2, 3);
+    }
+    events.kill(6000, 3, 2);
+    events.kill(7000, 2, 3);
+
+    assert_eq!(
+        vec![
+            (2000, Some(2), 2, TickRange::new(1000, 2000)),
+            (4000, Some(2), 4, TickRange::new(1000, 4000)),
+        ],
+        killstreaks(&events.finish())
+    );
+}
+
+/// The kind, tick, attacker and victim of every special kill
+fn special_kills(bookmarks: &Bookmarks) -> Vec<(&'static str, u32, u8, u8, String)> {
+    bookmarks
+        .highlights
+        .iter()
+        .filter_map(|highlight| {
+            let kill = match &highlight.source {
+                HighlightSource::Headshot { kill, .. }
+                | HighlightSource::Backstab(kill)
+                | HighlightSource::MarketGarden(kill)
+                | HighlightSource::Reflect(kill)
+                | HighlightSource::TauntKill(kill)
+                | HighlightSource::Telefrag(kill) => kill,
+                _ => return None,
+            };
+            assert_eq!(user(highlight), Some(kill.attacker.user_id.0));
+            Some((
+                highlight.source.kind(),
+                highlight.tick,
+                kill.attacker.user_id.0,
+                kill.victim.user_id.0,
+                kill.weapon.clone(),
+            ))
+        })
+        .collect()
+}
+
+#[test]
+fn test_special_kills() {
+    let mut events = Events::new(HighlightOptions {
+        killstreak_interval: 0,
+        ..HighlightOptions::default()
+    });
+    events.player(1, 2, RED, SPY);
+    events.player(1, 3, BLUE, SOLDIER);
+    // far enough apart to not be multi-kills
+    events.kill_with(1000, 2, 3, "machina", 1);
+    events.kill_with(2000, 2, 3, "machina", 14);
+    events.kill_with(3000, 2, 3, "sword", 51);
+    events.kill_with(4000, 2, 3, "knife", 2);
+    events.kill_with(5000, 2, 3, "telefrag", 16);
+    events.kill_with(6000, 2, 3, "taunt_spy", 13);
+    events.kill_with(7000, 3, 2, "deflect_rocket", 0);
+    events.kill_with(8000, 2, 3, "knife", 0);
+
+    let expected = [
+        ("headshot", 1000, 2, 3, "machina"),
+        ("headshot", 2000, 2, 3, "machina"),
+        ("headshot", 3000, 2, 3, "sword"),
+        ("backstab", 4000, 2, 3, "knife"),
+        ("telefrag", 5000, 2, 3, "telefrag"),
+        ("tauntKill", 6000, 2, 3, "taunt_spy"),
+        ("reflect", 7000, 3, 2, "deflect_rocket"),
+    ]
+    .map(|(kind, tick, attacker, victim, weapon)| (kind, tick, attacker, victim, weapon.into()));
+    assert_eq!(expected.to_vec(), special_kills(&events.finish()));
+}
+
+#[test]
+fn test_headshot_scope() {
+    let mut events = teams();
+    events.kill_with(1000, 2, 3, "machina", 14);
+
+    let bookmarks = events.finish();
+    match &bookmarks.highlights[0].source {
+        // only snipers get a scope
+        HighlightSource::Headshot { kill, scope } => {
+            assert_eq!(Some(Class::Soldier), kill.attacker.class);
+            assert_eq!(Some(Class::Scout), kill.victim.class);
+            assert_eq!(None, *scope);
+        }
+        source => panic!("expected a headshot, got {:?}", source),
+    }
+}
+
+#[test]
+fn test_market_garden() {
+    let mut events = teams();
+    events.event(
+        900,
+        GameEvent::RocketJump(RocketJumpEvent {
+            user_id: 2,
+            play_sound: true,
+        }),
+    );
+    events.kill_with(1000, 2, 3, "market_gardener", 0);
+    events.event(
+        1100,
+        GameEvent::RocketJumpLanded(RocketJumpLandedEvent { user_id: 2 }),
+    );
+    // not jumping anymore
+    events.kill_with(2000, 2, 4, "market_gardener", 0);
+
+    assert_eq!(
+        vec![("marketGarden", 1000, 2, 3, "market_gardener".to_string())],
+        special_kills(&events.finish())
+    );
+}
+
+fn airshots(bookmarks: &Bookmarks) -> Vec<(u32, Option<u8>, u8, u8, f32)> {
+    bookmarks
+        .highlights
+        .iter()
+        .filter_map(|highlight| match &highlight.source {
+            HighlightSource::AirShot {
+                attacker,
+                victim,
+                height,
+            } => Some((
+                highlight.tick,
+                user(highlight),
+                attacker.user_id.0,
+                victim.user_id.0,
+                *height,
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_airshot_weapons() {
+    let mut events = teams();
+    events.entity(10, 3, UpdateType::Enter, true, 100.0);
+    events.entity(20, 3, UpdateType::Preserve, false, 400.0);
+    // the default minimum airtime is 17 ticks at the default tick interval
+    events.hurt(30, 2, 3, WEAPON_DIRECTHIT);
+    events.hurt(40, 2, 3, WEAPON_DIRECTHIT);
+    events.hurt(50, 2, 3, WEAPON_ROCKETLAUNCHER);
+    events.hurt(60, 2, 3, WEAPON_SNIPERRIFLE);
+    // too low
+    events.entity(70, 3, UpdateType::Preserve, false, 150.0);
+    events.hurt(80, 2, 3, WEAPON_DIRECTHIT);
+
+    assert_eq!(
+        vec![(40, Some(2), 2, 3, 300.0), (50, Some(2), 2, 3, 300.0)],
+        airshots(&events.finish())
+    );
+}
+
+#[test]
+fn test_airshot_entity_left() {
+    let mut events = teams();
+    events.entity(10, 3, UpdateType::Enter, true, 100.0);
+    events.entity(20, 3, UpdateType::Preserve, false, 400.0);
+    // the player went out of view while in the air, their last known state is stale
+    events.entity(30, 3, UpdateType::Leave, false, 0.0);
+    events.hurt(40, 2, 3, WEAPON_DIRECTHIT);
+    // back in view on the ground
+    events.entity(50, 3, UpdateType::Enter, true, 1000.0);
+    events.hurt(60, 2, 3, WEAPON_DIRECTHIT);
+    events.entity(70, 3, UpdateType::Preserve, false, 1200.0);
+    events.hurt(90, 2, 3, WEAPON_DIRECTHIT);
+
+    assert_eq!(vec![(90, Some(2), 2, 3, 200.0)], airshots(&events.finish()));
+}
+
+#[test]
+fn test_airshot_ledge_drop() {
+    let mut events = teams();
+    events.entity(10, 3, UpdateType::Enter, true, 100.0);
+    // walked off a ledge, falling below where they last stood
+    events.entity(20, 3, UpdateType::Preserve, false, 90.0);
+    events.entity(40, 3, UpdateType::Preserve, false, -200.0);
+    events.hurt(50, 2, 3, WEAPON_DIRECTHIT);
+    events.entity(60, 3, UpdateType::Preserve, true, -300.0);
+    // the demo has no updates while the player rides up a lift, they walk off it at the top
+    events.entity(100, 3, UpdateType::Preserve, false, 0.0);
+    events.hurt(105, 2, 3, WEAPON_DIRECTHIT);
+    // rocket jumped from the lower ground
+    events.entity(150, 3, UpdateType::Preserve, true, -300.0);
+    events.entity(160, 3, UpdateType::Preserve, false, -200.0);
+    events.entity(180, 3, UpdateType::Preserve, false, 0.0);
+    events.hurt(185, 2, 3, WEAPON_DIRECTHIT);
+
+    assert_eq!(
+        vec![(185, Some(2), 2, 3, 300.0)],
+        airshots(&events.finish())
+    );
+}
+
+fn ubers(bookmarks: &Bookmarks) -> Vec<(&'static str, u32, Option<u8>, Option<Team>, f32)> {
+    bookmarks
+        .highlights
+        .iter()
+        .filter_map(|highlight| match &highlight.source {
+            HighlightSource::UberUsed(uber)
+            | HighlightSource::ForcedUber(uber)
+            | HighlightSource::UberDropped(uber)
+            | HighlightSource::UberAdvantage(uber) => {
+                assert_eq!(user(highlight), Some(uber.medic.user_id.0));
+                Some((
+                    highlight.source.kind(),
+                    highlight.tick,
+                    user(highlight),
+                    uber.team,
+                    uber.charge,
+                ))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// A medic and a soldier on both teams
+fn medics() -> Events {
+    let mut events = Events::new(HighlightOptions::default());
+    events.player(1, 2, RED, MEDIC);
+    events.player(1, 3, BLUE, MEDIC);
+    events.player(1, 4, RED, SOLDIER);
+    events.player(1, 5, BLUE, SOLDIER);
+    events
+}
+
+fn deploy(medic: u16) -> GameEvent {
+    GameEvent::PlayerChargeDeployed(PlayerChargeDeployedEvent {
+        user_id: medic,
+        target_id: 0,
+    })
+}
+
+fn medic_death(medic: u16, attacker: u16, charged: bool) -> GameEvent {
+    GameEvent::MedicDeath(MedicDeathEvent {
+        user_id: medic,
+        attacker,
+        healing: 0,
+        charged,
+    })
+}
+
+#[test]
+fn test_uber_used_and_forced() {
+    let mut events = medics();
+    events.event(1000, deploy(2));
+    // hurt to 50 health right before popping
+    events.hurt(2000, 5, 3, WEAPON_ROCKETLAUNCHER);
+    events.event(2010, deploy(3));
+    // hurt too long before popping
+    events.hurt(3000, 4, 3, WEAPON_ROCKETLAUNCHER);
+    events.event(4000, deploy(3));
+
+    assert_eq!(
+        vec![
+            ("uberUsed", 1000, Some(2), Some(Team::Red), 1.0),
+            ("forcedUber", 2010, Some(3), Some(Team::Blue), 1.0),
+            ("uberUsed", 4000, Some(3), Some(Team::Blue), 1.0),
+        ],
+        ubers(&events.finish())
+    );
+}
+
+#[test]
+fn test_uber_dropped() {
+    let mut events = medics();
+    events.event(1000, medic_death(2, 5, true));
+    events.event(1000, death(5, 2, "tf_projectile_rocket", 0));
+    events.event(2000, medic_death(3, 4, false));
+    events.event(2000, death(4, 3, "tf_projectile_rocket", 0));
+
+    assert_eq!(
+        vec![("uberDropped", 1000, Some(2), Some(Team::Red), 1.0)],
+        ubers(&events.finish())
+    );
+}
+
+#[test]
+fn test_uber_advantage() {
+    let mut events = medics();
+    let medigun = |owner: i64, charge: f32| {
+        vec![
+            (OWNER_ENTITY, SendPropValue::Integer(owner)),
+            (CHARGE_LEVEL, SendPropValue::Float(charge)),
+        ]
+    };
+    events.entities(
+        10,
+        vec![
+            (10, UpdateType::Enter, medigun(2, 0.5)),
+            (11, UpdateType::Enter, medigun(3, 0.5)),
+        ],
+    );
+    // red gets the first advantage
+    events.entities(100, vec![(10, UpdateType::Preserve, medigun(2, 1.0))]);
+    // both ready
+    events.entities(200, vec![(11, UpdateType::Preserve, medigun(3, 1.0))]);
+    // red used their uber, blue has the advantage
+    events.entities(300, vec![(10, UpdateType::Preserve, medigun(2, 0.0))]);
+    // blue used theirs too
+    events.entities(400, vec![(11, UpdateType::Preserve, medigun(3, 0.0))]);
+    events.entities(500, vec![(10, UpdateType::Preserve, medigun(2, 1.0))]);
+
+    assert_eq!(
+        vec![
+            ("uberAdvantage", 300, Some(3), Some(Team::Blue), 1.0),
+            ("uberAdvantage", 500, Some(2), Some(Team::Red), 1.0),
+        ],
+        ubers(&events.finish())
+    );
+}
+
+fn round_start() -> GameEvent {
+    GameEvent::TeamPlayRoundStart(TeamPlayRoundStartEvent { full_reset: false })
+}
+
+fn round_win(team: u8) -> GameEvent {
+    GameEvent::TeamPlayRoundWin(TeamPlayRoundWinEvent {
+        team,
+        win_reason: 0,
+        flag_cap_limit: 0,
+        full_round: 1,
+        round_time: 0.0,
+        losing_team_num_caps: 0,
+        was_sudden_death: 0,
+    })
+}
+
+/// The kind, tick and round of the round highlights
+fn rounds(bookmarks: &Bookmarks) -> Vec<(&'static str, u32, u32)> {
+    bookmarks
+        .highlights
+        .iter()
+        .filter_map(|highlight| {
+            let round = match &highlight.source {
+                HighlightSource::RoundStart { round }
+                | HighlightSource::RoundWin { round, .. }
+                | HighlightSource::RoundStalemate { round } => *round,
+                _ => return None,
+            };
+            assert_eq!(None, user(highlight));
+            Some((highlight.source.kind(), highlight.tick, round))
+        })
+        .collect()
+}
+
+#[test]
+fn test_round_flow() {
+    let mut events = teams();
+    // the demo starts during the first round
+    events.event(500, round_win(RED as u8));
+    events.event(1000, round_start());
+    // a restart replaces the running round
+    events.event(1100, round_start());
+    events.event(
+        2000,
+        GameEvent::TeamPlayRoundStalemate(TeamPlayRoundStalemateEvent { reason: 0 }),
+    );
+    events.event(3000, round_start());
+    events.event(4000, round_win(BLUE as u8));
+
+    let bookmarks = events.finish();
+    assert_eq!(
+        vec![
+            ("roundWin", 500, 1),
+            ("roundStart", 1000, 2),
+            ("roundStart", 1100, 2),
+            ("roundStalemate", 2000, 2),
+            ("roundStart", 3000, 3),
+            ("roundWin", 4000, 3),
+        ],
+        rounds(&bookmarks)
+    );
+    let winners = bookmarks
+        .highlights
+        .iter()
+        .filter_map(|highlight| match &highlight.source {
+            HighlightSource::RoundWin { team, .. } => Some(*team),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(vec![Team::Red, Team::Blue], winners);
+}
+
+#[test]
+fn test_objectives() {
+    let mut events = medics();
+    events.event(900, round_start());
+    events.event(
+        1000,
+        GameEvent::TeamPlayPointCaptured(TeamPlayPointCapturedEvent {
+            cp: 2,
+            cp_name: "Middle".to_string(),
+            team: RED as u8,
+            // one character with the entity index per capper
+            cappers: "\u{4}\u{2}".to_string(),
+        }),
+    );
+    events.event(
+        2000,
+        GameEvent::TeamPlayCaptureBlocked(TeamPlayCaptureBlockedEvent {
+            cp: 3,
+            cp_name: "Red second".to_string(),
+            blocker: 5,
+            victim: 4,
+        }),
+    );
+    let flag = |event_type| {
+        GameEvent::TeamPlayFlagEvent(TeamPlayFlagEventEvent {
+            player: 3,
+            carrier: 3,
+            event_type,
+            home: 0,
+            team: BLUE as u8,
+        })
+    };
+    // picked up
+    events.event(3000, flag(1));
+    // captured
+    events.event(3500, flag(2));
+
+    let bookmarks = events.finish();
+    let objectives = bookmarks
+        .highlights
+        .iter()
+        .filter(|highlight| !matches!(highlight.source, HighlightSource::RoundStart { .. }))
+        .collect::<Vec<_>>();
+    assert_eq!(3, objectives.len());
+
+    assert_eq!((1000, Some(4)), (objectives[0].tick, user(objectives[0])));
+    match &objectives[0].source {
+        HighlightSource::PointCaptured {
+            team,
+            round,
+            point,
+            name,
+            cappers,
+        } => {
+            assert_eq!(Team::Red, *team);
+            assert_eq!(1, *round);
+            assert_eq!(2, *point);
+            assert_eq!("Middle", name);
+            let cappers = cappers
+                .iter()
+                .map(|capper| capper.user_id.0)
+                .collect::<Vec<_>>();
+            assert_eq!(vec![4, 2], cappers);
+        }
+        source => panic!("expected a point capture, got {:?}", source),
+    }
+
+    assert_eq!((2000, Some(5)), (objectives[1].tick, user(objectives[1])));
+    match &objectives[1].source {
+        HighlightSource::PointBlocked { team, point, name } => {
+            assert_eq!(Team::Blue, *team);
+            assert_eq!(3, *point);
+            assert_eq!("Red second", name);
+        }
+        source => panic!("expected a blocked capture, got {:?}", source),
+    }
+
+    assert_eq!((3500, Some(3)), (objectives[2].tick, user(objectives[2])));
+    match &objectives[2].source {
+        HighlightSource::IntelCaptured { team } => assert_eq!(Team::Blue, *team),
+        source => panic!("expected an intel capture, got {:?}", source),
+    }
+}
+
+fn team_wipes(bookmarks: &Bookmarks) -> Vec<(u32, Team, u32, TickRange)> {
+    bookmarks
+        .highlights
+        .iter()
+        .filter_map(|highlight| match &highlight.source {
+            HighlightSource::TeamWipe { team, round, ticks } => {
+                assert_eq!(None, user(highlight));
+                Some((highlight.tick, *team, *round, *ticks))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_team_wipe() {
+    let mut events = teams();
+    events.event(50, round_start());
+    for (tick, victim) in [(100, 3), (200, 4), (300, 5), (400, 6)] {
+        events.kill(tick, 2, victim);
+    }
+
+    assert_eq!(
+        vec![(100, Team::Blue, 1, TickRange::new(100, 400))],
+        team_wipes(&events.finish())
+    );
+}
+
+#[test]
+fn test_team_wipe_window() {
+    let mut events = teams();
+    // the default window is 10 seconds, 667 ticks at the default tick interval
+    for (tick, victim) in [(100, 3), (1000, 4), (2000, 5), (2100, 6)] {
+        events.kill(tick, 2, victim);
+    }
+    // everyone respawned
+    for victim in 3..7 {
+        events.player(3000, victim, BLUE, SCOUT);
+    }
+    events.kill(3100, 2, 3);
+    events.kill(3200, 2, 4);
+    events.kill(3300, 2, 5);
+
+    assert!(team_wipes(&events.finish()).is_empty());
+}
+
+#[test]
+fn test_team_wipe_ignores_players_that_left() {
+    let mut events = teams();
+    events.kill(100, 2, 3);
+    events.kill(200, 2, 4);
+    events.kill(300, 2, 5);
+    // the last living player switches to spectator
+    events.event(
+        350,
+        GameEvent::PlayerTeam(PlayerTeamEvent {
+            user_id: 6,
+            team: 1,
+            old_team: BLUE as u8,
+            disconnect: false,
+            auto_team: false,
+            silent: false,
+            name: "player 6".to_string(),
+        }),
+    );
+    events.player(400, 7, BLUE, SCOUT);
+    events.kill(500, 2, 7);
+
+    assert_eq!(
+        vec![(100, Team::Blue, 1, TickRange::new(100, 500))],
+        team_wipes(&events.finish())
+    );
+}
+
+#[test]
+fn test_source_json() {
+    let mut events = teams();
+    events.event(50, round_start());
+    events.kill(100, 2, 3);
+    events.kill(200, 2, 4);
+
+    let bookmarks = events.finish();
+    assert_eq!(2, bookmarks.highlights.len());
+    for highlight in &bookmarks.highlights {
+        let json = serde_json::to_value(&highlight.source).unwrap();
+        assert_eq!(highlight.source.kind(), json["kind"]);
+    }
+
+    let multi_kill = bookmarks
+        .highlights
+        .iter()
+        .find(|highlight| matches!(highlight.source, HighlightSource::MultiKill { .. }))
+        .unwrap();
+    assert_eq!(
+        serde_json::json!({
+            "kind": "multiKill",
+            "multiKill": "double",
+            "ticks": {"start": 100, "end": 200},
+        }),
+        serde_json::to_value(&multi_kill.source).unwrap()
+    );
+}
@@ -0,0 +1,107 @@
+use democutter::{cut, try_cut_with_index, DemoIndex, IndexError};
+use std::fs;
+use std::path::PathBuf;
+
+#[test]
+fn test_index_round_trip() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let index = DemoIndex::new(&file, 4000).unwrap();
+    let loaded = DemoIndex::from_bytes(&index.to_bytes(), &file).unwrap();
+
+    assert!(!index.keyframes().is_empty());
+    assert_eq!(index.keyframes().len(), loaded.keyframes().len());
+    for (original, loaded) in index.keyframes().iter().zip(loaded.keyframes()) {
+        assert_eq!(original.position, loaded.position);
+        assert_eq!(original.offset, loaded.offset);
+    }
+
+    assert_eq!(
+        try_cut_with_index(&file, &index, 30000, 50000).unwrap(),
+        try_cut_with_index(&file, &loaded, 30000, 50000).unwrap()
+    );
+}
+
+#[test]
+fn test_index_mismatch() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let other = fs::read("test_data/icewind_85000_90300.dem").unwrap();
+    let index = DemoIndex::new(&file, 4000).unwrap();
+
+    assert!(matches!(
+        DemoIndex::from_bytes(&index.to_bytes(), &other),
+        Err(IndexError::Mismatch)
+    ));
+    assert!(matches!(
+        DemoIndex::from_bytes(b"garbage", &file),
+        Err(IndexError::InvalidMagic)
+    ));
+}
+
+fn temp_demo(name: &str, data: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("democutter-{}-{}", std::process::id(), name));
+    fs::write(&path, data).unwrap();
+    path
+}
+
+/// A copy of the demo with trailing bytes after the stop packet, which don't change the parsed demo
+fn with_trailer(file: &[u8], trailer: u8) -> Vec<u8> {
+    let mut data = file.to_vec();
+    data.extend_from_slice(&[trailer; 16]);
+    data
+}
+
+/// Save a sidecar for `original`, replace the demo with `changed` and check that the sidecar is ignored
+fn assert_stale_sidecar(name: &str, original: &[u8], changed: &[u8]) {
+    let path = temp_demo(name, original);
+    let index = DemoIndex::new(original, 4000).unwrap();
+    index.save_sidecar(&path).unwrap();
+    fs::write(&path, changed).unwrap();
+
+    assert!(DemoIndex::load_sidecar(&path, changed).unwrap().is_none());
+    assert!(!index.matches(changed));
+    assert_eq!(
+        cut(changed, 30000, 50000),
+        try_cut_with_index(changed, &index, 30000, 50000).unwrap()
+    );
+
+    fs::remove_file(DemoIndex::sidecar_path(&path)).unwrap();
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_stale_sidecar_size() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    assert_stale_sidecar("stale-size.dem", &file, &with_trailer(&file, 0));
+}
+
+#[test]
+fn test_stale_sidecar_hash() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let original = with_trailer(&file, 0);
+    let changed = with_trailer(&file, 1);
+    assert_eq!(original.len(), changed.len());
+    assert_stale_sidecar("stale-hash.dem", &original, &changed);
+}
+
+#[test]
+fn test_sidecar_version() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let path = temp_demo("stale-version.dem", &file);
+    let index = DemoIndex::new(&file, 4000).unwrap();
+    index.save_sidecar(&path).unwrap();
+    assert!(DemoIndex::load_sidecar(&path, &file).unwrap().is_some());
+
+    // the version follows the 4 magic bytes
+    let mut data = index.to_bytes();
+    data[4] = data[4].wrapping_add(1);
+    fs::write(DemoIndex::sidecar_path(&path), &data).unwrap();
+
+    assert!(matches!(
+        DemoIndex::from_bytes(&data, &file),
+        Err(IndexError::UnsupportedVersion(_))
+    ));
+    assert!(DemoIndex::load_sidecar(&path, &file).unwrap().is_none());
+
+    fs::remove_file(DemoIndex::sidecar_path(&path)).unwrap();
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,74 @@
+use democutter::{demo_info, try_demo_info};
+use std::fs;
+use tf_demo_parser::demo::header::Header;
+use tf_demo_parser::demo::packet::Packet;
+use tf_demo_parser::demo::parser::DemoHandler;
+use tf_demo_parser::{Demo, Parse};
+
+/// Byte offset of the tick count in the demo header
+const HEADER_TICKS_OFFSET: usize = 1060;
+
+/// The tick of the last message packet in the demo
+fn last_message_tick(input: &[u8]) -> u32 {
+    let demo = Demo::new(&input);
+    let mut stream = demo.get_stream();
+    let header = Header::read(&mut stream).unwrap();
+
+    let mut handler = DemoHandler::default();
+    handler.handle_header(&header);
+
+    let mut last_tick = 0;
+    loop {
+        let packet = Packet::parse(&mut stream, &handler.state_handler).unwrap();
+        match &packet {
+            Packet::Stop(_) => break,
+            Packet::Message(_) => last_tick = packet.tick(),
+            _ => {}
+        }
+        handler.handle_packet(packet).unwrap();
+    }
+    last_tick
+}
+
+#[test]
+fn test_demo_info() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let info = demo_info(&file);
+
+    assert!(!info.map.is_empty());
+    assert!(info.ticks > 0);
+    assert!((info.tick_interval - 1.0 / 66.0).abs() < 0.001);
+    assert!((info.duration - info.ticks as f32 * info.tick_interval).abs() < 1.0);
+    assert!(!info.players.is_empty());
+    assert!(info
+        .players
+        .iter()
+        .all(|player| player.steam_id.starts_with("[U:") || player.steam_id == "BOT"));
+
+    for round in &info.rounds {
+        if let Some(end) = round.end_tick {
+            assert!(end >= round.start_tick);
+        }
+    }
+}
+
+#[test]
+fn test_demo_info_invalid() {
+    assert!(try_demo_info(b"not a demo").is_err());
+}
+
+#[test]
+fn test_demo_info_without_header_ticks() {
+    let mut file = fs::read("test_data/gully.dem").unwrap();
+    let last_tick = last_message_tick(&file);
+    let original = demo_info(&file);
+
+    // demos that are still being recorded have no length in the header
+    file[HEADER_TICKS_OFFSET..HEADER_TICKS_OFFSET + 4].fill(0);
+    let info = demo_info(&file);
+
+    assert_eq!(last_tick, info.ticks);
+    assert!((info.duration - last_tick as f32 * info.tick_interval).abs() < 0.01);
+    assert_eq!(original.players, info.players);
+    assert_eq!(original.rounds, info.rounds);
+}
@@ -0,0 +1,105 @@
+use democutter::{cut, try_cut_with_index, DemoIndex};
+use pretty_assertions::assert_eq;
+use std::fs;
+use tf_demo_parser::demo::header::Header;
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::demo::packet::Packet;
+use tf_demo_parser::demo::parser::DemoHandler;
+use tf_demo_parser::{Demo, Parse};
+
+/// Collect the frame time and deviation of every net tick in the demo
+fn net_timings(input: &[u8]) -> Vec<(u32, u16, u16)> {
+    let demo = Demo::new(&input);
+    let mut stream = demo.get_stream();
+    let header = Header::read(&mut stream).unwrap();
+
+    let mut handler = DemoHandler::default();
+    handler.handle_header(&header);
+
+    let mut timings = Vec::new();
+    loop {
+        let packet = Packet::parse(&mut stream, &handler.state_handler).unwrap();
+        match &packet {
+            Packet::Stop(_) => break,
+            Packet::Message(message_packet) => {
+                for message in &message_packet.messages {
+                    if let Message::NetTick(net_tick) = message {
+                        timings.push((net_tick.tick, net_tick.frame_time, net_tick.std_dev));
+                    }
+                }
+            }
+            _ => {}
+        }
+        handler.handle_packet(packet).unwrap();
+    }
+    timings
+}
+
+/// The net ticks of the packets synthesized at the start of a cut
+///
+/// The start sequence covers the two ticks before the last delta and the delta tick itself,
+/// the net ticks copied from the source demo after it are all later.
+fn start_sequence(input: &[u8]) -> Vec<(u32, u16, u16)> {
+    let timings = net_timings(input);
+    let first_tick = timings[0].0;
+    timings
+        .into_iter()
+        .take_while(|(tick, _, _)| *tick <= first_tick + 2)
+        .collect()
+}
+
+/// The timing the source demo has around `tick`, interpolating between the surrounding net ticks
+fn source_timing(source: &[(u32, u16, u16)], tick: u32) -> (u16, u16) {
+    let after = source.partition_point(|(seen, _, _)| *seen < tick);
+    let next = source[after];
+    if next.0 == tick {
+        return (next.1, next.2);
+    }
+    let previous = source[after
+        .checked_sub(1)
+        .expect("no source net tick before the start")];
+    let progress = (tick - previous.0) as f32 / (next.0 - previous.0) as f32;
+    let lerp = |a: u16, b: u16| (a as f32 + (b as f32 - a as f32) * progress).round() as u16;
+    (lerp(previous.1, next.1), lerp(previous.2, next.2))
+}
+
+#[test]
+fn test_net_tick_timing_from_source() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let output = cut(&file, 30000, 50000);
+
+    let source = net_timings(&file);
+    let start = start_sequence(&output);
+
+    // at least a packet for the string tables, the entities and the removed entities
+    assert!(start.len() >= 3, "start sequence {:?}", start);
+    let delta_tick = start.last().unwrap().0;
+    assert_eq!(vec![delta_tick - 2, delta_tick - 1, delta_tick], {
+        let mut ticks = start.iter().map(|(tick, _, _)| *tick).collect::<Vec<_>>();
+        ticks.dedup();
+        ticks
+    });
+
+    for (tick, frame_time, std_dev) in start {
+        assert_eq!(
+            source_timing(&source, tick),
+            (frame_time, std_dev),
+            "net tick {}",
+            tick
+        );
+    }
+}
+
+#[test]
+fn test_net_tick_timing_indexed() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let index = DemoIndex::new(&file, 4000).unwrap();
+
+    // start right after a keyframe, so the timings can only come from the keyframe
+    let start = index.keyframes()[7].position.tick + 1;
+    let output = cut(&file, start, start + 2000);
+    let indexed = try_cut_with_index(&file, &index, start, start + 2000).unwrap();
+
+    assert_eq!(start_sequence(&output), start_sequence(&indexed));
+    assert_eq!(net_timings(&output), net_timings(&indexed));
+}
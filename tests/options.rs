@@ -0,0 +1,104 @@
+use democutter::{cut, try_cut_with_options, CutOptions, TypeFilter};
+use std::fs;
+use tf_demo_parser::demo::header::Header;
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::demo::packet::{Packet, PacketType};
+use tf_demo_parser::demo::parser::DemoHandler;
+use tf_demo_parser::{Demo, MessageType, Parse};
+
+/// Count the packet and message types in a demo
+fn count_types(input: &[u8]) -> (usize, Vec<MessageType>) {
+    let demo = Demo::new(&input);
+    let mut stream = demo.get_stream();
+    let header = Header::read(&mut stream).unwrap();
+
+    let mut handler = DemoHandler::default();
+    handler.handle_header(&header);
+
+    let mut console_commands = 0;
+    let mut message_types = Vec::new();
+    loop {
+        let packet = Packet::parse(&mut stream, &handler.state_handler).unwrap();
+        match &packet {
+            Packet::Stop(_) => break,
+            Packet::Message(message_packet) => message_types.extend(
+                message_packet
+                    .messages
+                    .iter()
+                    .map(Message::get_message_type),
+            ),
+            _ if packet.packet_type() == PacketType::ConsoleCmd => console_commands += 1,
+            _ => {}
+        }
+        handler.handle_packet(packet).unwrap();
+    }
+    (console_commands, message_types)
+}
+
+#[test]
+fn test_default_options_match_cut() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    assert_eq!(
+        cut(&file, 30000, 50000),
+        try_cut_with_options(&file, 30000, 50000, &CutOptions::default()).unwrap()
+    );
+}
+
+#[test]
+fn test_console_commands() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let (console_commands, _) = count_types(&cut(&file, 30000, 50000));
+    assert_eq!(0, console_commands);
+
+    let options = CutOptions {
+        keep_console_commands: true,
+        ..CutOptions::default()
+    };
+    let output = try_cut_with_options(&file, 30000, 50000, &options).unwrap();
+    let (console_commands, _) = count_types(&output);
+    assert!(console_commands > 0);
+}
+
+#[test]
+fn test_drop_messages() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let options = CutOptions {
+        messages: TypeFilter::Drop(vec![MessageType::TempEntities, MessageType::PacketEntities]),
+        ..CutOptions::default()
+    };
+    let output = try_cut_with_options(&file, 30000, 50000, &options).unwrap();
+    let (_, message_types) = count_types(&output);
+    assert!(!message_types.contains(&MessageType::TempEntities));
+    // required for playback and never dropped
+    assert!(message_types.contains(&MessageType::PacketEntities));
+}
+
+#[test]
+fn test_preserve_ticks() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let options = CutOptions {
+        preserve_ticks: true,
+        ..CutOptions::default()
+    };
+    let output = try_cut_with_options(&file, 30000, 50000, &options).unwrap();
+
+    let demo = Demo::new(&output);
+    let mut stream = demo.get_stream();
+    let header = Header::read(&mut stream).unwrap();
+    assert_eq!(50000, header.ticks);
+
+    let mut handler = DemoHandler::default();
+    handler.handle_header(&header);
+    loop {
+        let packet = Packet::parse(&mut stream, &handler.state_handler).unwrap();
+        match &packet {
+            Packet::Stop(stop) => {
+                assert_eq!(50000, stop.tick);
+                break;
+            }
+            Packet::Message(message_packet) => assert!(message_packet.tick >= 30000),
+            _ => {}
+        }
+        handler.handle_packet(packet).unwrap();
+    }
+}
@@ -0,0 +1,67 @@
+use democutter::{
+    cut, try_bookmarks_with_progress, try_cut_ranges_with_progress, CutError, CutOptions,
+    HighlightOptions, TickRange,
+};
+use std::fs;
+use std::io::Cursor;
+use std::ops::ControlFlow;
+
+#[test]
+fn test_cut_progress() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let mut reports = Vec::new();
+    let (output, _) = try_cut_ranges_with_progress(
+        &file,
+        Cursor::new(Vec::new()),
+        &[TickRange::new(30000, 50000)],
+        None,
+        &CutOptions::default(),
+        |progress| {
+            reports.push(progress);
+            ControlFlow::Continue(())
+        },
+    )
+    .unwrap();
+    assert_eq!(cut(&file, 30000, 50000), output.into_inner());
+
+    assert!(reports.len() > 1);
+    assert!(reports
+        .windows(2)
+        .all(|pair| pair[0].bytes <= pair[1].bytes && pair[0].tick <= pair[1].tick));
+    let last = reports.last().unwrap();
+    assert_eq!(file.len(), last.bytes);
+    assert_eq!(file.len(), last.total_bytes);
+    assert_eq!(1.0, last.fraction());
+}
+
+#[test]
+fn test_cancel_cut() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let mut calls = 0;
+    let result = try_cut_ranges_with_progress(
+        &file,
+        Cursor::new(Vec::new()),
+        &[TickRange::new(30000, 50000)],
+        None,
+        &CutOptions::default(),
+        |_| {
+            calls += 1;
+            if calls > 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+    );
+    assert!(matches!(result, Err(CutError::Cancelled { .. })));
+    assert_eq!(3, calls);
+}
+
+#[test]
+fn test_cancel_bookmarks() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let result = try_bookmarks_with_progress(&file, &HighlightOptions::default(), |_| {
+        ControlFlow::Break(())
+    });
+    assert!(matches!(result, Err(CutError::Cancelled { .. })));
+}
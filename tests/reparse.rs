@@ -1,20 +1,21 @@
-use democutter::cut;
+use democutter::{cut, try_cut_with_index, DemoIndex};
 use pretty_assertions::assert_eq;
 use std::fs;
 use tf_demo_parser::demo::message::packetentities::EntityId;
 use tf_demo_parser::demo::message::Message;
 use tf_demo_parser::demo::packet::message::MessagePacketMeta;
-use tf_demo_parser::demo::parser::gamestateanalyser::GameStateAnalyser;
+use tf_demo_parser::demo::parser::gamestateanalyser::{GameState, GameStateAnalyser};
 use tf_demo_parser::demo::parser::handler::BorrowMessageHandler;
 use tf_demo_parser::demo::parser::MessageHandler;
 use tf_demo_parser::demo::sendprop::SendProp;
 use tf_demo_parser::{Demo, DemoParser, MessageType, ParserState};
 
 fn test_reparse_with_analyser<A: BorrowMessageHandler + Default, F: Fn(&A::Output, &A::Output)>(
+    cutter: fn(&[u8]) -> Vec<u8>,
     f: F,
 ) {
     let file = fs::read("test_data/gully.dem").unwrap();
-    let output = cut(&file, 30000, 50000);
+    let output = cutter(&file);
 
     let original = Demo::new(&file);
     let cut = Demo::new(&output);
@@ -58,13 +59,15 @@ fn test_reparse_with_analyser<A: BorrowMessageHandler + Default, F: Fn(&A::Outpu
     }
 }
 
+type EntityDump = Vec<(EntityId, (Vec<SendProp>, Vec<SendProp>))>;
+
 #[derive(Default)]
 struct EntityDumper {
-    entities: Vec<(EntityId, (Vec<SendProp>, Vec<SendProp>))>,
+    entities: EntityDump,
 }
 
 impl MessageHandler for EntityDumper {
-    type Output = Vec<(EntityId, (Vec<SendProp>, Vec<SendProp>))>;
+    type Output = EntityDump;
 
     fn does_handle(message_type: MessageType) -> bool {
         match message_type {
@@ -102,22 +105,45 @@ impl BorrowMessageHandler for EntityDumper {
     }
 }
 
+fn cut_plain(input: &[u8]) -> Vec<u8> {
+    cut(input, 30000, 50000)
+}
+
+fn cut_indexed(input: &[u8]) -> Vec<u8> {
+    let index = DemoIndex::new(input, 4000).unwrap();
+    try_cut_with_index(input, &index, 30000, 50000).unwrap()
+}
+
+fn assert_game_state_eq(original_state: &GameState, cut_state: &GameState) {
+    assert_eq!(original_state.world, cut_state.world);
+    assert_eq!(original_state.players, cut_state.players);
+    assert_eq!(original_state.buildings, cut_state.buildings);
+}
+
+fn assert_entities_eq(original_state: &EntityDump, cut_state: &EntityDump) {
+    assert_eq!(original_state.len(), cut_state.len());
+    for (original_entity, cut_entity) in original_state.iter().zip(cut_state) {
+        assert_eq!(original_entity.0, cut_entity.0);
+        assert_eq!(original_entity.1, cut_entity.1);
+    }
+}
+
 #[test]
 fn test_reparse_game_state() {
-    test_reparse_with_analyser::<GameStateAnalyser, _>(|original_state, cut_state| {
-        assert_eq!(original_state.world, cut_state.world);
-        assert_eq!(original_state.players, cut_state.players);
-        assert_eq!(original_state.buildings, cut_state.buildings);
-    })
+    test_reparse_with_analyser::<GameStateAnalyser, _>(cut_plain, assert_game_state_eq)
 }
 
 #[test]
 fn test_reparse_entities() {
-    test_reparse_with_analyser::<EntityDumper, _>(|original_state, cut_state| {
-        assert_eq!(original_state.len(), cut_state.len());
-        for (original_entity, cut_entity) in original_state.iter().zip(cut_state) {
-            assert_eq!(original_entity.0, cut_entity.0);
-            assert_eq!(original_entity.1, cut_entity.1);
-        }
-    })
+    test_reparse_with_analyser::<EntityDumper, _>(cut_plain, assert_entities_eq)
+}
+
+#[test]
+fn test_reparse_game_state_indexed() {
+    test_reparse_with_analyser::<GameStateAnalyser, _>(cut_indexed, assert_game_state_eq)
+}
+
+#[test]
+fn test_reparse_entities_indexed() {
+    test_reparse_with_analyser::<EntityDumper, _>(cut_indexed, assert_entities_eq)
 }
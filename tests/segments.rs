@@ -0,0 +1,151 @@
+use democutter::{cut_ranges, TickRange};
+use pretty_assertions::assert_eq;
+use std::collections::BTreeMap;
+use std::fs;
+use tf_demo_parser::demo::message::Message;
+use tf_demo_parser::demo::packet::stringtable::StringTableEntry;
+use tf_demo_parser::demo::parser::gamestateanalyser::{GameState, GameStateAnalyser};
+use tf_demo_parser::demo::parser::handler::BorrowMessageHandler;
+use tf_demo_parser::demo::parser::MessageHandler;
+use tf_demo_parser::{Demo, DemoParser, MessageType, ParserState};
+
+/// Number of ticks after the start of a segment at which the states are compared,
+/// to leave room for the synthesized start packets
+const SETTLE_TICKS: u32 = 10;
+
+/// Compare the state of the cut with the source demo at the start of every segment
+///
+/// `ranges` have to be sorted and non-overlapping so they are cut as given
+fn test_segment_states<A: BorrowMessageHandler + Default, F: Fn(&A::Output, &A::Output)>(
+    ranges: &[TickRange],
+    f: F,
+) {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let output = cut_ranges(&file, ranges);
+
+    let original = Demo::new(&file);
+    let cut = Demo::new(&output);
+
+    let original_parser = DemoParser::new_with_analyser(original.get_stream(), A::default());
+    let cut_parser = DemoParser::new_with_analyser(cut.get_stream(), A::default());
+
+    let mut original_ticks = original_parser.ticker().unwrap().1;
+    let mut cut_ticks = cut_parser.ticker().unwrap().1;
+
+    let mut output_start = 0;
+    let mut compared = 0;
+    for range in ranges {
+        while let Some(original_tick) = original_ticks.next().unwrap() {
+            if original_tick.tick < range.start + SETTLE_TICKS {
+                continue;
+            }
+            while let Some(cut_tick) = cut_ticks.next().unwrap() {
+                if cut_tick.tick < output_start + SETTLE_TICKS {
+                    continue;
+                }
+                assert_eq!(
+                    original_tick.tick,
+                    cut_tick.tick - output_start + range.start
+                );
+                f(&original_tick.state, &cut_tick.state);
+                compared += 1;
+                break;
+            }
+            break;
+        }
+
+        output_start += range.len();
+    }
+    assert_eq!(ranges.len(), compared);
+}
+
+/// The text and extra data length of the latest entry for every index of every string table,
+/// the content of the userinfo extra data is compared by the game state tests
+type StringTableDump = BTreeMap<(String, usize), (Option<String>, Option<u16>)>;
+
+#[derive(Default)]
+struct StringTableDumper {
+    tables: StringTableDump,
+}
+
+impl MessageHandler for StringTableDumper {
+    type Output = StringTableDump;
+
+    fn does_handle(_message_type: MessageType) -> bool {
+        false
+    }
+
+    fn handle_message(&mut self, _message: &Message, _tick: u32) {}
+
+    fn handle_string_entry(&mut self, table: &str, index: usize, entry: &StringTableEntry) {
+        self.tables.insert(
+            (table.to_string(), index),
+            (
+                entry.text.as_ref().map(|text| text.to_string()),
+                entry.extra_data.as_ref().map(|extra| extra.byte_len),
+            ),
+        );
+    }
+
+    fn into_output(self, _state: &ParserState) -> Self::Output {
+        self.tables
+    }
+}
+
+impl BorrowMessageHandler for StringTableDumper {
+    fn borrow_output(&self, _state: &ParserState) -> &Self::Output {
+        &self.tables
+    }
+}
+
+fn assert_game_state_eq(original_state: &GameState, cut_state: &GameState) {
+    assert_eq!(original_state.world, cut_state.world);
+    assert_eq!(original_state.players, cut_state.players);
+    assert_eq!(original_state.buildings, cut_state.buildings);
+}
+
+fn assert_string_tables_eq(original_state: &StringTableDump, cut_state: &StringTableDump) {
+    assert_eq!(original_state, cut_state);
+}
+
+const SEGMENTS: &[TickRange] = &[
+    TickRange {
+        start: 12000,
+        end: 13500,
+    },
+    TickRange {
+        start: 30000,
+        end: 31000,
+    },
+    TickRange {
+        start: 40200,
+        end: 41000,
+    },
+];
+
+#[test]
+fn test_single_range_state() {
+    test_segment_states::<GameStateAnalyser, _>(&SEGMENTS[1..2], assert_game_state_eq);
+}
+
+#[test]
+fn test_multiple_ranges_game_state() {
+    test_segment_states::<GameStateAnalyser, _>(SEGMENTS, assert_game_state_eq);
+}
+
+#[test]
+fn test_multiple_ranges_string_tables() {
+    test_segment_states::<StringTableDumper, _>(SEGMENTS, assert_string_tables_eq);
+}
+
+#[test]
+fn test_multiple_ranges_reparse() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    // ranges are sorted before cutting
+    let output = cut_ranges(&file, &[SEGMENTS[0], SEGMENTS[2], SEGMENTS[1]]);
+
+    let demo = Demo::new(&output);
+    let (header, state) = DemoParser::new(demo.get_stream()).parse().unwrap();
+    assert_eq!(1500 + 800 + 1000, header.ticks);
+    assert!(!state.users.is_empty());
+}
@@ -0,0 +1,45 @@
+use democutter::{
+    cut, cut_file_to_writer, cut_ranges, cut_to_writer, map_demo, try_cut_ranges_to_stream,
+    CutOptions, TickRange,
+};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+#[test]
+fn test_stream_matches_cut() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let expected = cut(&file, 30000, 50000);
+
+    // a plain vec can't seek, so the header has to be known before writing
+    let output = cut_to_writer(&file, Vec::new(), TickRange::new(30000, 50000))
+        .unwrap()
+        .0;
+    assert_eq!(expected, output);
+
+    let output = cut_file_to_writer(
+        Path::new("test_data/gully.dem"),
+        Cursor::new(Vec::new()),
+        TickRange::new(30000, 50000),
+    )
+    .unwrap()
+    .0;
+    assert_eq!(expected, output.into_inner());
+
+    let map = map_demo(Path::new("test_data/gully.dem")).unwrap();
+    let output = cut_to_writer(&map, Vec::new(), TickRange::new(30000, 50000))
+        .unwrap()
+        .0;
+    assert_eq!(expected, output);
+}
+
+#[test]
+fn test_stream_ranges_matches_cut() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let ranges = [TickRange::new(12000, 13500), TickRange::new(30000, 31000)];
+
+    let (output, report) =
+        try_cut_ranges_to_stream(&file, Vec::new(), &ranges, None, &CutOptions::default()).unwrap();
+    assert_eq!(cut_ranges(&file, &ranges), output);
+    assert_eq!(output.len() as u64, report.output_size);
+}
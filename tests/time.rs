@@ -0,0 +1,65 @@
+use democutter::{duration_to_ticks, format_time, parse_time, ticks_to_duration, CutPoint};
+use std::time::Duration;
+
+#[test]
+fn test_parse_time() {
+    assert_eq!(Ok(Duration::from_secs(90)), parse_time("90s"));
+    assert_eq!(Ok(Duration::from_secs(90)), parse_time("1:30"));
+    assert_eq!(
+        Ok(Duration::from_millis(3_723_500)),
+        parse_time("01:02:03.5")
+    );
+    assert!(parse_time("1:75").is_err());
+    assert!(parse_time("1:2:3:4").is_err());
+    assert!(parse_time("foo").is_err());
+}
+
+#[test]
+fn test_parse_time_out_of_range() {
+    assert!(parse_time("1e20s").is_err());
+    assert!(parse_time("1e400s").is_err());
+    assert!(parse_time("infs").is_err());
+    assert!(parse_time("NaNs").is_err());
+    assert!(parse_time("-5s").is_err());
+    assert!(parse_time("-1:00").is_err());
+    assert!(parse_time("18446744073709551615:00").is_err());
+    assert!(parse_time("18446744073709551615:00:00").is_err());
+    assert!("1e20s".parse::<CutPoint>().is_err());
+}
+
+#[test]
+fn test_duration_to_ticks_saturates() {
+    assert_eq!(u32::MAX, duration_to_ticks(Duration::MAX, 1.0 / 66.0));
+    assert_eq!(
+        u32::MAX,
+        duration_to_ticks(Duration::from_secs(1 << 40), 0.015)
+    );
+    assert_eq!(Duration::MAX, ticks_to_duration(u32::MAX, f32::MAX));
+    // invalid intervals fall back to the default interval instead of dividing by 0
+    assert_eq!(
+        duration_to_ticks(Duration::from_secs(10), 0.015),
+        duration_to_ticks(Duration::from_secs(10), 0.0)
+    );
+    assert_eq!(
+        duration_to_ticks(Duration::from_secs(10), 0.015),
+        duration_to_ticks(Duration::from_secs(10), f32::NAN)
+    );
+}
+
+#[test]
+fn test_cut_point() {
+    assert_eq!(Ok(CutPoint::Tick(1234)), "1234".parse());
+    assert_eq!(Ok(CutPoint::Time(Duration::from_secs(60))), "1:00".parse());
+    assert_eq!(
+        660,
+        CutPoint::Time(Duration::from_secs(10)).to_tick(1.0 / 66.0)
+    );
+}
+
+#[test]
+fn test_format_time() {
+    assert_eq!(
+        "01:02:03.500",
+        format_time(Duration::from_millis(3_723_500))
+    );
+}
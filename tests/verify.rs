@@ -0,0 +1,71 @@
+use democutter::{cut, try_cut_with_options, verify_cut, CutOptions, TickRange};
+use std::fs;
+
+#[test]
+fn test_verify_cut() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let output = cut(&file, 30000, 50000);
+
+    let report = verify_cut(&file, &output, TickRange::new(30000, 50000)).unwrap();
+    assert!(report.is_ok(), "{}", report);
+    assert!(report.ticks_compared > 0);
+}
+
+#[test]
+fn test_verify_preserved_ticks() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let options = CutOptions {
+        preserve_ticks: true,
+        ..CutOptions::default()
+    };
+    let output = try_cut_with_options(&file, 30000, 50000, &options).unwrap();
+
+    let report = verify_cut(&file, &output, TickRange::new(30000, 50000)).unwrap();
+    assert!(report.is_ok(), "{}", report);
+}
+
+#[test]
+fn test_verify_normal_and_preserved_ticks() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let preserve = CutOptions {
+        preserve_ticks: true,
+        ..CutOptions::default()
+    };
+
+    // short ranges, where a last packet past the end of the range makes up a large part of the cut
+    for range in [
+        TickRange::new(30000, 30050),
+        TickRange::new(12000, 12100),
+        TickRange::new(30000, 50000),
+    ] {
+        let normal =
+            try_cut_with_options(&file, range.start, range.end, &CutOptions::default()).unwrap();
+        let preserved = try_cut_with_options(&file, range.start, range.end, &preserve).unwrap();
+
+        let normal_report = verify_cut(&file, &normal, range).unwrap();
+        assert!(normal_report.is_ok(), "{:?}: {}", range, normal_report);
+        let preserved_report = verify_cut(&file, &preserved, range).unwrap();
+        assert!(
+            preserved_report.is_ok(),
+            "{:?}: {}",
+            range,
+            preserved_report
+        );
+        assert_eq!(
+            normal_report.ticks_compared,
+            preserved_report.ticks_compared
+        );
+    }
+}
+
+#[test]
+fn test_verify_wrong_range() {
+    let file = fs::read("test_data/gully.dem").unwrap();
+    let output = cut(&file, 30000, 50000);
+
+    let report = verify_cut(&file, &output, TickRange::new(31000, 51000)).unwrap();
+    let divergence = report
+        .divergence
+        .expect("cut of a different range verified");
+    assert!(divergence.tick > 31000);
+}
@@ -0,0 +1,20 @@
+// Cutting runs in a worker for the same reasons as the highlight scan in `scan-worker.js`,
+// a running cut is cancelled by terminating the worker.
+const democutter = import("democutter");
+
+self.addEventListener('message', (event) => {
+    democutter.then(({cutWithProgress}) => {
+        let {data, start, end} = event.data;
+        let result;
+        try {
+            result = cutWithProgress(data, start, end, null, (progress) => {
+                self.postMessage({type: "progress", progress});
+            });
+        } catch (e) {
+            self.postMessage({type: "error", error: `${e}`});
+            return;
+        }
+        let output = result.data;
+        self.postMessage({type: "done", data: output, report: result.report}, [output.buffer]);
+    });
+});
@@ -1,43 +1,193 @@
-import {cut} from "democutter";
+import {demoInfo, tickInterval} from "democutter";
 
 
 let fileSelect = document.getElementById('file');
 let startInput = document.getElementById('start');
 let endInput = document.getElementById('end');
 let cutButton = document.getElementById('cut');
+let status = document.getElementById('status');
+let info = document.getElementById('info');
+let highlightList = document.getElementById('highlights');
+let scanStatus = document.getElementById('scan');
+let scanProgress = document.getElementById('scan-progress');
+let cancelScanButton = document.getElementById('cancel-scan');
+let cutStatus = document.getElementById('cutting');
+let cutProgress = document.getElementById('cut-progress');
+let cancelCutButton = document.getElementById('cancel-cut');
 
 let outputName = "cut.dem";
+let scanWorker = null;
+let cutWorker = null;
 
 cutButton.addEventListener('click', (event) => {
     let start = parseInt(startInput.value);
     let end = parseInt(endInput.value);
-    console.log(start, end);
     fileSelect.disabled = true;
     let reader = new FileReader();
     reader.readAsArrayBuffer(fileSelect.files[0]);
     reader.addEventListener('load', () => {
-        console.log(reader.result);
-        let result = cut(new Uint8Array(reader.result), start, end);
         fileSelect.disabled = false;
-        save(result, outputName);
+        cutDemo(new Uint8Array(reader.result), start, end);
     });
 });
 
+cancelCutButton.addEventListener('click', () => {
+    stopCut();
+    status.textContent = "Cut cancelled";
+});
+
+function stopCut() {
+    if (cutWorker) {
+        cutWorker.terminate();
+        cutWorker = null;
+    }
+    cutStatus.hidden = true;
+    cutButton.disabled = false;
+}
+
+function cutDemo(data, start, end) {
+    stopCut();
+    let worker = new Worker("./cut-worker.js");
+    cutWorker = worker;
+    cutProgress.value = 0;
+    cutStatus.hidden = false;
+    cutButton.disabled = true;
+    status.textContent = "";
+    worker.addEventListener('message', (event) => {
+        let message = event.data;
+        switch (message.type) {
+            case "progress":
+                cutProgress.value = progressValue(message.progress);
+                break;
+            case "error":
+                stopCut();
+                status.textContent = `Failed to cut demo: ${message.error}`;
+                break;
+            case "done": {
+                stopCut();
+                let report = message.report;
+                if (report.clamped) {
+                    let ranges = report.ranges.map(range => `${range.start}-${range.end}`).join(", ");
+                    status.textContent = `Clipped to ${ranges}`;
+                }
+                save(message.data, outputName);
+                break;
+            }
+        }
+    });
+    worker.postMessage({data, start, end}, [data.buffer]);
+}
+
 
 fileSelect.addEventListener('change', (event) => {
-    const tickRate = 66;
-    let name = fileSelect.files[0].name;
+    let file = fileSelect.files[0];
+    let name = file.name;
+    scanDemo(file);
     let match = name.match(/^([^_]+)_(\d+)\.dem$/);
     if (match) {
-        let highlightTick = parseInt(match[2]);
-        startInput.value = highlightTick;
-        endInput.value = highlightTick + tickRate * 5 * 60;
         outputName = `${match[1]}_cut.dem`;
+        // the header is enough to get the tick rate of the demo
+        let reader = new FileReader();
+        reader.readAsArrayBuffer(file.slice(0, 1072));
+        reader.addEventListener('load', () => {
+            let tickRate = 66;
+            try {
+                tickRate = Math.round(1 / tickInterval(new Uint8Array(reader.result)));
+            } catch (e) {
+                console.error(e);
+            }
+            let highlightTick = parseInt(match[2]);
+            startInput.value = highlightTick;
+            endInput.value = highlightTick + tickRate * 5 * 60;
+        });
     } else {
         outputName = name.replace(/\.dem/, "_cut.dem");
     }
 });
 
+cancelScanButton.addEventListener('click', () => {
+    stopScan();
+});
+
+function stopScan() {
+    if (scanWorker) {
+        scanWorker.terminate();
+        scanWorker = null;
+    }
+    scanStatus.hidden = true;
+}
+
+function scanDemo(file) {
+    stopScan();
+    info.textContent = "";
+    highlightList.textContent = "";
+    let reader = new FileReader();
+    reader.readAsArrayBuffer(file);
+    reader.addEventListener('load', () => {
+        let data = new Uint8Array(reader.result);
+        let demo;
+        try {
+            demo = demoInfo(data);
+        } catch (e) {
+            console.error(e);
+            return;
+        }
+        let minutes = Math.floor(demo.duration / 60);
+        let seconds = Math.floor(demo.duration % 60).toString().padStart(2, "0");
+        info.textContent = `${demo.map} on ${demo.server}, ${minutes}:${seconds} (${demo.ticks} ticks), ` +
+            `${demo.players.length} players, ${demo.rounds.length} rounds`;
+
+        scanHighlights(data);
+    });
+}
+
+function scanHighlights(data) {
+    let worker = new Worker("./scan-worker.js");
+    scanWorker = worker;
+    scanProgress.value = 0;
+    scanStatus.hidden = false;
+    worker.addEventListener('message', (event) => {
+        let message = event.data;
+        switch (message.type) {
+            case "progress":
+                scanProgress.value = progressValue(message.progress);
+                break;
+            case "error":
+                console.error(message.error);
+                stopScan();
+                break;
+            case "done":
+                stopScan();
+                showHighlights(message.highlights);
+                break;
+        }
+    });
+    worker.postMessage(data, [data.buffer]);
+}
+
+// The fraction of the demo that has been processed
+function progressValue(progress) {
+    return progress.totalBytes ? progress.bytes / progress.totalBytes : 1;
+}
+
+function showHighlights(highlights) {
+    for (let highlight of highlights) {
+        let item = document.createElement("li");
+        let link = document.createElement("a");
+        link.href = "#";
+        link.textContent = highlight.player
+            ? `${highlight.tick}: ${highlight.kind} by ${highlight.player.name}`
+            : `${highlight.tick}: ${highlight.kind}`;
+        link.addEventListener('click', (event) => {
+            event.preventDefault();
+            startInput.value = highlight.suggestedRange.start;
+            endInput.value = highlight.suggestedRange.end;
+        });
+        item.appendChild(link);
+        highlightList.appendChild(item);
+    }
+}
+
 function save(data, fileName) {
     let a = document.createElement("a");
     document.body.appendChild(a);
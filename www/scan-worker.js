@@ -0,0 +1,18 @@
+// The highlight scan runs in a worker to keep the page responsive, a running scan is cancelled
+// by terminating the worker. Like in `bootstrap.js` the wasm has to be imported asynchronously.
+const democutter = import("democutter");
+
+self.addEventListener('message', (event) => {
+    democutter.then(({scanHighlightsWithProgress}) => {
+        let highlights;
+        try {
+            highlights = scanHighlightsWithProgress(event.data, null, (progress) => {
+                self.postMessage({type: "progress", progress});
+            });
+        } catch (e) {
+            self.postMessage({type: "error", error: `${e}`});
+            return;
+        }
+        self.postMessage({type: "done", highlights});
+    });
+});
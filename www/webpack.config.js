@@ -1,7 +1,7 @@
 const CopyWebpackPlugin = require("copy-webpack-plugin");
 const path = require('path');
 
-module.exports = {
+module.exports = [{
   entry: "./bootstrap.js",
   output: {
     path: path.resolve(__dirname, "dist"),
@@ -11,4 +11,16 @@ module.exports = {
   plugins: [
     new CopyWebpackPlugin(['index.html'])
   ],
-};
+}, {
+  entry: {
+    "scan-worker": "./scan-worker.js",
+    "cut-worker": "./cut-worker.js",
+  },
+  target: "webworker",
+  output: {
+    path: path.resolve(__dirname, "dist"),
+    filename: "[name].js",
+    chunkFilename: "worker.[id].js",
+  },
+  mode: "development",
+}];